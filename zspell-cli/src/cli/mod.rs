@@ -13,6 +13,16 @@ pub struct Cli {
     #[arg(short = 'd', long)]
     pub dict_path: Option<String>,
 
+    /// Path to a compiled dictionary cache. The cache is loaded if it is up to
+    /// date with the dictionary, otherwise it is rebuilt
+    #[arg(long)]
+    pub cache: Option<PathBuf>,
+
+    /// Personal dictionary file to load. While spellchecking, enter `*word` to accept a
+    /// word; it is added to the dictionary and appended to this file
+    #[arg(short = 'p', long)]
+    pub personal: Option<PathBuf>,
+
     /// Whether to print misspelled words
     #[arg(short = 'l', long, default_value_t = false)]
     pub misspelled_words: bool,
@@ -21,10 +31,21 @@ pub struct Cli {
     #[arg(short = 'L', long, default_value_t = false)]
     pub misspelled_lines: bool,
 
-    /// Print the a compiled dictionary's word list to stdout and exit
+    /// Print every word form of the dictionary to stdout, sorted, and exit
     #[arg(long, default_value_t = false)]
     pub generate_wordlist: bool,
 
+    /// With `--generate-wordlist`, also print words that are never suggested
+    /// (prefixed with `!`), substandard words (prefixed with `~`) and forbidden words
+    /// (prefixed with `*`)
+    #[arg(long, default_value_t = false, requires = "generate_wordlist")]
+    pub all_forms: bool,
+
+    /// Compress a word list file (one word per line) into a dictionary file using the
+    /// dictionary's affix rules, print it to stdout and exit
+    #[arg(long, value_name = "WORDLIST")]
+    pub munch: Option<PathBuf>,
+
     /// Enable morpological analysis mode
     #[arg(short = 'm', long, default_value_t = false)]
     pub analyze: bool,
@@ -33,6 +54,11 @@ pub struct Cli {
     #[arg(short = 's', long, default_value_t = false)]
     pub stem: bool,
 
+    /// Enable word generation mode. Each input line is a stem followed by
+    /// morphological fields, e.g. `drink is:past`
+    #[arg(short = 'g', long, default_value_t = false)]
+    pub generate: bool,
+
     /// Print the search path and found dictionaries
     #[arg(short = 'D', long, default_value_t = false)]
     pub show_dictionaries: bool,
@@ -47,8 +73,9 @@ pub struct Cli {
 
 impl Cli {
     pub fn validate(&self) -> Result<(), String> {
-        if self.analyze && self.stem {
-            Err("cannot use analysis and stemming together".into())
+        let modes = [self.analyze, self.stem, self.generate];
+        if modes.iter().filter(|enabled| **enabled).count() > 1 {
+            Err("cannot use analysis, stemming and generation together".into())
         } else {
             Ok(())
         }
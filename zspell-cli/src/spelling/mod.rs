@@ -1,12 +1,14 @@
 //! Helpers for CLI spelling features
 
+use std::fs::{self, OpenOptions};
 use std::io::{self, BufRead, Write};
+use std::path::Path;
 use std::process::ExitCode;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use zspell::error::Error;
-use zspell::system::{create_dict_from_path, PKG_NAME, PKG_VERSION};
-use zspell::Dictionary;
+use zspell::system::{create_dict_from_path, create_dict_from_path_cached, PKG_NAME, PKG_VERSION};
+use zspell::{Dictionary, FormKind, MorphInfo};
 
 use crate::cli::Cli;
 
@@ -36,7 +38,11 @@ pub fn spellcheck_cli(cli: &Cli) -> ExitCode {
     };
 
     let load_start = Instant::now();
-    let dict = match create_dict_from_path(dict_path) {
+    let loaded = match cli.cache.as_ref() {
+        Some(cache_path) => create_dict_from_path_cached(dict_path, cache_path),
+        None => create_dict_from_path(dict_path),
+    };
+    let mut dict = match loaded {
         Ok(v) => v,
         Err(e) => {
             match e {
@@ -49,21 +55,38 @@ pub fn spellcheck_cli(cli: &Cli) -> ExitCode {
             return ExitCode::FAILURE;
         }
     };
+
+    if let Some(personal_path) = cli.personal.as_ref() {
+        // A missing file is fine, it is created when the first word is accepted
+        let personal = fs::read_to_string(personal_path).unwrap_or_default();
+        if let Err(e) = dict.add_personal_str(&personal) {
+            eprintln!("Error loading personal dictionary: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
     let load_time = load_start.elapsed().as_secs_f32();
     let wc = dict.wordlist().inner().len() + dict.wordlist_nosuggest().inner().len();
     eprintln!("loaded {wc} words in {load_time:.2}s. started session");
 
-    if cli.generate_wordlist {
-        todo!();
-        // for item in dic.iter_wordlist_items().unwrap() {
-        //     println!("{item}");
-        // }
+    if let Some(wordlist_path) = cli.munch.as_ref() {
+        match fs::read_to_string(wordlist_path) {
+            Ok(words) => print!("{}", dict.munch(&words)),
+            Err(e) => {
+                eprintln!("could not read {}: {e}", wordlist_path.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if cli.generate_wordlist {
+        runner_generate_wordlist(&dict, cli.all_forms);
     } else if cli.analyze {
         runner_morph_analysis(&dict);
     } else if cli.stem {
         runner_stemming(&dict);
+    } else if cli.generate {
+        runner_generate(&dict);
     } else {
-        runner_spellcheck(&dict);
+        runner_spellcheck(&mut dict, cli.personal.as_deref());
     }
 
     // Quick RNG without external crates
@@ -78,14 +101,68 @@ pub fn spellcheck_cli(cli: &Cli) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-fn runner_spellcheck(dict: &Dictionary) {
+/// Print every word form in the dictionary once, sorted. Nosuggest, substandard and
+/// forbidden words are marked with `!`, `~` and `*` if they are included.
+fn runner_generate_wordlist(dict: &Dictionary, all_forms: bool) {
+    let mut lines: Vec<String> = dict
+        .unmunch()
+        .filter_map(|form| {
+            let marker = match form.kind() {
+                FormKind::Accepted => "",
+                FormKind::NoSuggest if all_forms => "!",
+                FormKind::Substandard if all_forms => "~",
+                FormKind::Forbidden if all_forms => "*",
+                _ => return None,
+            };
+            Some(format!("{marker}{}", form.word()))
+        })
+        .collect();
+
+    lines.sort_unstable();
+    lines.dedup();
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    for line in lines {
+        writeln!(out, "{line}").expect("failed to write to stdout");
+    }
+}
+
+/// Print misspelled words. Lines starting with `*` accept a word, adding it to the
+/// personal dictionary file if there is one.
+fn runner_spellcheck(dict: &mut Dictionary, personal: Option<&Path>) {
     stdin_interactive_runner(|line| {
+        if let Some(word) = line.strip_prefix('*').map(str::trim) {
+            if word.is_empty() {
+                return;
+            }
+            dict.add_word(word);
+            if let Some(path) = personal {
+                if let Err(e) = append_personal(path, word) {
+                    eprintln!("could not write to {}: {e}", path.display());
+                }
+            }
+            return;
+        }
+
         for (_, misspelled) in dict.check_indices(&line) {
             println!("{misspelled}");
         }
     })
 }
 
+/// Append a single word to a personal dictionary file, creating it if needed. A `/` in
+/// the word is escaped so it is not read as the start of a friend.
+fn append_personal(path: &Path, word: &str) -> io::Result<()> {
+    // Don't join the word to an unterminated last line
+    let needs_newline = matches!(fs::read(path).as_deref(), Ok([.., last]) if *last != b'\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", word.replace('/', "\\/"))
+}
+
 /// From hunspell:
 ///
 /// ```text
@@ -114,28 +191,69 @@ fn runner_stemming(dict: &Dictionary) {
     })
 }
 
+/// Each line is a stem followed by morphological fields. Print the stem with each
+/// word that is created from it and has those fields:
+///
+/// ```text
+/// drink is:past
+/// drink drank
+///
+/// walk is:past
+/// walk walked
+/// ```
+fn runner_generate(dict: &Dictionary) {
+    stdin_interactive_runner(|line| {
+        let mut fields = line.split_whitespace();
+        let Some(stem) = fields.next() else {
+            return;
+        };
+        let morphs: Vec<MorphInfo> = fields.map(MorphInfo::from).collect();
+
+        let words = dict.generate(stem, &morphs);
+        if words.is_empty() {
+            println!("{stem}");
+        }
+        for word in words {
+            println!("{stem} {word}");
+        }
+        println!();
+    })
+}
+
+/// Same format as hunspell, with one line per analysis and a blank line after each
+/// word. Unknown words are printed on their own:
+///
+/// ```text
+/// drinks st:drink po:noun is:plural
+/// drinks st:drink po:verb is:sg_3
+///
+/// dofjjos
+/// ```
 fn runner_morph_analysis(dict: &Dictionary) {
     stdin_interactive_runner(|line| {
         for entry in dict.entries(&line) {
-            print!("{}", entry.word());
+            let Some(analyses) = entry.analyses() else {
+                println!("{}\n", entry.word());
+                continue;
+            };
 
-            if let Some(morphs) = entry.analyze() {
-                for morph in morphs {
+            for analysis in analyses {
+                print!("{}", entry.word());
+                for morph in analysis {
                     print!(" {morph}");
                 }
-            } else {
-                println!("{}", entry.word())
+                println!();
             }
+            println!();
         }
-        println!("\n");
     })
 }
 
 /// Common runner interface that calls a function once per stdin line
 // FIXME: if not a tty, lock output once before writing
-fn stdin_interactive_runner<F>(f: F)
+fn stdin_interactive_runner<F>(mut f: F)
 where
-    F: Fn(String),
+    F: FnMut(String),
 {
     let stdin = io::stdin();
     // This is a false positive, see clippy #9135
@@ -1 +1,154 @@
 //! Tests for the dictionary command line interface
+
+use std::fs;
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+#[test]
+fn personal_accept_words() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let dict_path = dir.path().join("test");
+    let personal_path = dir.path().join("personal.dic");
+    fs::write(dir.path().join("test.aff"), "SFX S Y 1\nSFX S 0 s .\n")?;
+    fs::write(dir.path().join("test.dic"), "1\napple/S\n")?;
+    fs::write(&personal_path, "banana")?;
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d")
+        .arg(&dict_path)
+        .arg("--personal")
+        .arg(&personal_path)
+        .write_stdin("apples banana zspell\n*zspell\n*km/h\nzspell cherry\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::eq("zspell\ncherry\n"));
+
+    // Slashes are escaped so they are not read as a friend
+    assert_eq!(
+        fs::read_to_string(&personal_path)?,
+        "banana\nzspell\nkm\\/h\n"
+    );
+
+    // Accepted words are loaded in the next session
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d")
+        .arg(&dict_path)
+        .arg("-p")
+        .arg(&personal_path)
+        .write_stdin("zspell banana cherry\n");
+    cmd.assert().success().stdout(predicate::eq("cherry\n"));
+
+    Ok(())
+}
+
+#[test]
+fn generate_wordlist() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let dict_path = dir.path().join("test");
+    fs::write(
+        dir.path().join("test.aff"),
+        "NOSUGGEST !\nFORBIDDENWORD *\nSUBSTANDARD ~\nPFX U Y 1\nPFX U 0 un .\nSFX S Y 1\nSFX S 0 s .\n",
+    )?;
+    fs::write(
+        dir.path().join("test.dic"),
+        "5\nlock/US\nbanana/S\ndamn/!\nirregardless/*\nain't/~\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d").arg(&dict_path).arg("--generate-wordlist");
+    cmd.assert().success().stdout(predicate::eq(
+        "banana\nbananas\nlock\nlocks\nunlock\nunlocks\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d")
+        .arg(&dict_path)
+        .arg("--generate-wordlist")
+        .arg("--all-forms");
+    cmd.assert().success().stdout(predicate::eq(
+        "!damn\n*irregardless\nbanana\nbananas\nlock\nlocks\nunlock\nunlocks\n~ain't\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn munch_wordlist() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let dict_path = dir.path().join("test");
+    let wordlist_path = dir.path().join("words.txt");
+    fs::write(
+        dir.path().join("test.aff"),
+        "PFX U Y 1\nPFX U 0 un .\nSFX S Y 1\nSFX S 0 s .\n",
+    )?;
+    fs::write(dir.path().join("test.dic"), "0\n")?;
+    fs::write(&wordlist_path, "lock\nlocks\nunlock\nunlocks\ncherry\n")?;
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d")
+        .arg(&dict_path)
+        .arg("--munch")
+        .arg(&wordlist_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::eq("2\ncherry\nlock/SU\n"));
+
+    Ok(())
+}
+
+#[test]
+fn generate_forms() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let dict_path = dir.path().join("test");
+    fs::write(
+        dir.path().join("test.aff"),
+        "SFX D Y 1\nSFX D 0 ed . is:past\nSFX S Y 1\nSFX S 0 s . is:plural\n",
+    )?;
+    fs::write(
+        dir.path().join("test.dic"),
+        "3\nwalk/DS po:verb\ndrink/S po:verb\ndrank st:drink is:past\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d")
+        .arg(&dict_path)
+        .arg("--generate")
+        .write_stdin("drink is:past\nwalk is:past\nwalk po:verb is:plural\nbanana\n");
+    cmd.assert().success().stdout(predicate::eq(
+        "drink drank\n\nwalk walked\n\nwalk walks\n\nbanana\n\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d").arg(&dict_path).arg("-g").arg("-s");
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn morph_analysis() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let dict_path = dir.path().join("test");
+    fs::write(
+        dir.path().join("test.aff"),
+        "SFX S Y 1\nSFX S 0 s . is:plural\nSFX X Y 1\nSFX X 0 able . ds:able\n",
+    )?;
+    fs::write(
+        dir.path().join("test.dic"),
+        "2\ndrink/X po:verb\ndrink/S po:noun\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin("zspell")?;
+    cmd.arg("-d")
+        .arg(&dict_path)
+        .arg("-m")
+        .write_stdin("drinkable drinks dofjjos\n");
+    cmd.assert().success().stdout(predicate::eq(
+        "drinkable st:drink po:verb ds:able\n\n\
+         drinks st:drink po:noun is:plural\n\n\
+         dofjjos\n\n",
+    ));
+
+    Ok(())
+}
@@ -4,8 +4,8 @@ use std::fs;
 use std::hint::black_box;
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use zspell::bench::{affix_from_str, DictEntry, FlagType};
-use zspell::{DictBuilder, Dictionary};
+use zspell::bench::{affix_from_str, DictEntry, ParsedCfg};
+use zspell::{CompactDictionary, DictBuilder, Dictionary, Strategy};
 
 const TEXT: &str = "A Hare was mking fun of the Tortoise one day for being so slow.
 
@@ -85,12 +85,9 @@ pub fn bench_parsers(c: &mut Criterion) {
         b.iter(|| black_box(affix_from_str(black_box(&aff_content)).unwrap()))
     });
 
+    let cfg = ParsedCfg::load_from_str(&aff_content).unwrap();
     c.bench_function("Parse dict file", |b| {
-        b.iter(|| {
-            black_box(
-                DictEntry::parse_all(black_box(&dic_content), black_box(FlagType::Utf8)).unwrap(),
-            )
-        })
+        b.iter(|| black_box(DictEntry::parse_all(black_box(&dic_content), &cfg).unwrap()))
     });
 }
 
@@ -147,12 +144,91 @@ pub fn bench_dict_paragraph(c: &mut Criterion) {
     });
 }
 
+/// Loading and checking when affixes are stripped rather than expanded
+pub fn bench_dict_lazy(c: &mut Criterion) {
+    let aff_content = fs::read_to_string("../dictionaries/en_US.aff").unwrap();
+    let dic_content = fs::read_to_string("../dictionaries/en_US.dic").unwrap();
+    let builder = DictBuilder::new()
+        .dict_str(&dic_content)
+        .config_str(&aff_content)
+        .expansion(Strategy::Lazy);
+
+    c.bench_function("Spellcheck lazy: compile dictionary", |b| {
+        b.iter(|| black_box(black_box(builder.clone()).build().unwrap()))
+    });
+
+    let dict = builder.build().unwrap();
+    c.bench_function("Spellcheck lazy: 1 correct word", |b| {
+        b.iter(|| black_box(dict.check_word(black_box("turbidity's"))))
+    });
+
+    c.bench_function("Spellcheck lazy: 188 word paragraph", |b| {
+        b.iter(|| black_box(dict.check(black_box(TEXT))))
+    });
+}
+
+/// Compare building on one thread and multiple threads
+#[cfg(feature = "parallel")]
+pub fn bench_parallel(c: &mut Criterion) {
+    let aff_content = fs::read_to_string("../dictionaries/en_US.aff").unwrap();
+    let dic_content = fs::read_to_string("../dictionaries/en_US.dic").unwrap();
+    let builder = DictBuilder::new()
+        .dict_str(&dic_content)
+        .config_str(&aff_content);
+
+    let mut group = c.benchmark_group("Spellcheck: compile dictionary threads");
+    for (name, parallel) in [("serial", false), ("parallel", true)] {
+        let builder = builder.clone().parallel(parallel);
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(black_box(builder.clone()).build().unwrap()))
+        });
+
+        let builder = builder.expansion(Strategy::Lazy);
+        group.bench_function(format!("{name} lazy"), |b| {
+            b.iter(|| black_box(black_box(builder.clone()).build().unwrap()))
+        });
+    }
+    group.finish();
+}
+
+/// Checking with the read-only compact format
+pub fn bench_dict_compact(c: &mut Criterion) {
+    let aff_content = fs::read_to_string("../dictionaries/en_US.aff").unwrap();
+    let dic_content = fs::read_to_string("../dictionaries/en_US.dic").unwrap();
+    let bytes = DictBuilder::new()
+        .dict_str(&dic_content)
+        .config_str(&aff_content)
+        .build_compact()
+        .unwrap();
+
+    c.bench_function("Spellcheck compact: load dictionary", |b| {
+        b.iter(|| black_box(CompactDictionary::new(black_box(bytes.as_slice())).unwrap()))
+    });
+
+    let dict = CompactDictionary::new(bytes.as_slice()).unwrap();
+    c.bench_function("Spellcheck compact: 1 correct word", |b| {
+        b.iter(|| black_box(dict.check_word(black_box("turbidity's"))))
+    });
+
+    c.bench_function("Spellcheck compact: 188 word paragraph", |b| {
+        b.iter(|| black_box(dict.check(black_box(TEXT))))
+    });
+}
+
 criterion_group!(
     dict_integration,
     bench_parsers,
     bench_dict_compile,
     bench_dict_simple,
     bench_dict_paragraph,
-    // bench_parallel,
+    bench_dict_lazy,
+    bench_dict_compact,
 );
+
+#[cfg(feature = "parallel")]
+criterion_group!(dict_parallel, bench_parallel);
+
+#[cfg(feature = "parallel")]
+criterion_main!(dict_integration, dict_parallel);
+#[cfg(not(feature = "parallel"))]
 criterion_main!(dict_integration);
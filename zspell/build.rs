@@ -55,6 +55,15 @@ fn update_tests() {
                     let dict = mgr.build_dict();
                     mgr.check_all(&dict);
                 }}
+
+                #[test]
+                fn test_{test_name}_lazy() {{
+                    let path = std::path::Path::new(env!(\"CARGO_MANIFEST_DIR\"));
+                    let path = path.join(\"test-suite/{fname}\");
+                    let mgr = test_util::TestManager::new_from_file(path);
+                    let dict = mgr.build_dict_with(zspell::Strategy::Lazy);
+                    mgr.check_lazy(&dict);
+                }}
             "},
             test_name = test_name,
             fname = fname,
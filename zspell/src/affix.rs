@@ -5,6 +5,7 @@ mod parse;
 mod rule;
 mod types;
 
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
@@ -14,7 +15,8 @@ pub use self::types::{
     RuleType,
 };
 use crate::dict::{AfxRule, Flag, FlagValue};
-use crate::error::{BuildError, Error, ParseError};
+use crate::error::{BuildError, Error, ParseError, ParseErrorKind};
+use crate::morph::MorphInfo;
 
 /// A representation of an affix file
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -22,9 +24,8 @@ pub struct ParsedCfg {
     /*
         General Options
     */
-    /// Charset to use, reference to an [`Encoding`] Currently this is
-    /// unused; only UTF-8 is supported. However, the affix file must still have
-    /// an accurate definition.
+    /// Charset of the affix and dictionary files, from `SET`. Input given as bytes is
+    /// decoded with this encoding; input given as `&str` is already UTF-8.
     encoding: Encoding,
 
     /// The type of flag in the `.dic` file
@@ -40,10 +41,12 @@ pub struct ParsedCfg {
     /// List of characters to ignore
     ignore_chars: Vec<char>,
 
-    /// List of usable flag vectors. Defaults to all things after "/"" in a dict.
+    /// Flag vectors from `AF`. If these exist, flags in the dictionary and in affix
+    /// continuation classes are 1-based indices into this list.
     affix_alias: Vec<String>,
 
-    /// List of usable flag vectors
+    /// Morphological information from `AM`. If these exist, morph fields that are a
+    /// number are 1-based indices into this list.
     morph_alias: Vec<String>,
 
     /*
@@ -70,7 +73,7 @@ pub struct ParsedCfg {
     /// If a dot comes with the spellcheck, return one with a suggestion word
     keep_term_dots: bool,
 
-    /// Whether to never suggest words with the warn flag (above)
+    /// Whether to reject words with the warn flag (above), from `FORBIDWARN`
     forbid_warn_words: bool,
 
     /// Replace commonly misused letters, e.g. `u`/`ü`
@@ -244,10 +247,45 @@ impl Default for CompoundConfig {
 }
 
 impl ParsedCfg {
+    #[inline]
     pub fn flag_type(&self) -> FlagType {
         self.flag_type
     }
 
+    /// Charset of the source files (`SET`)
+    #[inline]
+    pub(crate) fn encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    /// Language code of the dictionary (`LANG`), empty if unspecified
+    #[inline]
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    /// Characters that are part of words in addition to alphanumerics (`WORDCHARS`)
+    pub(crate) fn word_chars(&self) -> &str {
+        &self.afx_word_chars
+    }
+
+    /// Whether words flagged with `WARN` should be rejected (`FORBIDWARN`)
+    pub(crate) fn forbid_warn_words(&self) -> bool {
+        self.forbid_warn_words
+    }
+
+    /// Whether affix rules are allowed to strip the entire stem (`FULLSTRIP`)
+    #[inline]
+    pub fn afx_full_strip(&self) -> bool {
+        self.afx_full_strip
+    }
+
+    /// Whether to use twofold prefixes rather than twofold suffixes (`COMPLEXPREFIXES`)
+    #[inline]
+    pub fn complex_prefixes(&self) -> bool {
+        self.complex_prefixes
+    }
+
     /// Create a `Config` object from a string version of an affix file
     ///
     /// # Errors
@@ -370,6 +408,20 @@ impl ParsedCfg {
             }
         }
 
+        // Affix rules may be defined before `AM`, so resolve their aliases at the end
+        if !res.morph_alias.is_empty() {
+            let mut groups = std::mem::take(&mut res.afx_rule_groups);
+            for rule in groups.iter_mut().flat_map(|group| group.rules.iter_mut()) {
+                if let [morph] = rule.morph_info.as_slice() {
+                    let morph_str = morph.to_string();
+                    rule.morph_info = res
+                        .parse_morphs(&morph_str)
+                        .map_err(|e| ParseError::new_nospan(e, &morph_str))?;
+                }
+            }
+            res.afx_rule_groups = groups;
+        }
+
         for w in warnings {
             eprintln!("warning: {w}");
         }
@@ -384,8 +436,87 @@ impl ParsedCfg {
             .map_err(|e| ParseError::new_nospan(e, flag))
     }
 
+    /// Parse a string of flags from a dictionary entry or affix continuation class. If
+    /// `AF` aliases are defined, the string is a 1-based index into the alias table.
+    pub(crate) fn parse_flags(&self, s: &str) -> Result<Vec<Flag>, ParseErrorKind> {
+        if self.affix_alias.is_empty() {
+            return self.flag_type.parse_str(s);
+        }
+
+        let index: usize = s.parse().map_err(ParseErrorKind::Int)?;
+        let alias = index
+            .checked_sub(1)
+            .and_then(|i| self.affix_alias.get(i))
+            .ok_or(ParseErrorKind::AliasIndex {
+                index,
+                count: self.affix_alias.len(),
+            })?;
+
+        self.flag_type.parse_str(alias)
+    }
+
+    /// Flags of each `AF` alias, in order. Empty if no aliases are defined.
+    pub(crate) fn affix_aliases(&self) -> Vec<Vec<Flag>> {
+        self.affix_alias
+            .iter()
+            .map(|alias| self.flag_type.parse_str(alias).unwrap_or_default())
+            .collect()
+    }
+
+    /// Remove characters listed in `IGNORE` from a string
+    pub(crate) fn strip_ignored<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if self.ignore_chars.is_empty() || !s.contains(self.ignore_chars.as_slice()) {
+            Cow::Borrowed(s)
+        } else {
+            s.chars()
+                .filter(|ch| !self.ignore_chars.contains(ch))
+                .collect()
+        }
+    }
+
+    /// Prepare a word for lookup: apply `ICONV` conversions, then remove `IGNORE`
+    /// characters
+    pub(crate) fn normalize_input<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match Conversion::apply_all(&self.input_conversions, s) {
+            Cow::Borrowed(b) => self.strip_ignored(b),
+            Cow::Owned(o) => Cow::Owned(self.strip_ignored(&o).into_owned()),
+        }
+    }
+
+    /// Prepare a word for output: apply `OCONV` conversions
+    pub(crate) fn convert_output<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        Conversion::apply_all(&self.output_conversions, s)
+    }
+
+    /// Whether `AM` morph aliases are defined
+    pub(crate) fn has_morph_alias(&self) -> bool {
+        !self.morph_alias.is_empty()
+    }
+
+    /// Parse morphological information from a dictionary entry or affix rule. If `AM`
+    /// aliases are defined, a number is a 1-based index into the alias table.
+    pub(crate) fn parse_morphs(&self, s: &str) -> Result<Vec<Arc<MorphInfo>>, ParseErrorKind> {
+        let morph_str = match s.parse::<usize>() {
+            Ok(index) if !self.morph_alias.is_empty() => index
+                .checked_sub(1)
+                .and_then(|i| self.morph_alias.get(i))
+                .ok_or(ParseErrorKind::AliasIndex {
+                    index,
+                    count: self.morph_alias.len(),
+                })?,
+            _ => s,
+        };
+
+        Ok(MorphInfo::many_from_str(morph_str).map(Arc::new).collect())
+    }
+
     /// Collect all relevant flags to a map. Returns an error if there are
     /// duplicates
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a flag is defined more than once or cannot be parsed
+    #[inline]
     pub fn compile_flags(&self) -> Result<CompiledFlags, Error> {
         // FIXME(circumfix): these flags probably need to be split differently
 
@@ -465,7 +596,7 @@ impl ParsedCfg {
                 .into());
             }
 
-            let rule = AfxRule::from_parsed_group(self, group);
+            let rule = AfxRule::from_parsed_group(self, group)?;
             affix_flags.insert(flag, FlagValue::Rule(Arc::new(rule)));
         }
 
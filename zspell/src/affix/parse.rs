@@ -369,12 +369,8 @@ fn parse_affix_alias(s: &str) -> ParseResult {
     })
 }
 fn parse_morph_alias(s: &str) -> ParseResult {
-    table_parser(s, "AM", |v| {
-        for (i, item) in v.iter().enumerate() {
-            check_contains_whitespace(item).map_err(|e| e.add_offset_ret(i + 1, 0))?;
-        }
-        Ok(AffixNode::MorphAlias(v))
-    })
+    // Morph info is whitespace-separated, so unlike `AF` we allow it here
+    table_parser(s, "AM", |v| Ok(AffixNode::MorphAlias(v)))
 }
 
 /*
@@ -28,6 +28,8 @@ pub struct ParsedRuleGroup {
 pub struct ParsedRule {
     /// Affix to be added
     pub(crate) affix: String,
+    /// Continuation flags (the part after `/` in the affix), not yet converted to flags
+    pub(crate) cont_flags: Option<String>,
     /// Characters to remove from the beginning or end
     pub(crate) strip: Option<Arc<str>>,
     /// Regex-based rule for when this rule is true. `None` indicates `.`, i.e.,
@@ -54,6 +56,7 @@ impl ParsedRule {
         Ok(Self {
             strip: strip.map(Into::into),
             affix: affix.to_owned(),
+            cont_flags: None,
             condition: cond_re,
             morph_info,
         })
@@ -76,6 +79,7 @@ impl ParsedRule {
         Ok(Self {
             strip: strip.map(Into::into),
             affix: affix.to_owned(),
+            cont_flags: None,
             condition: cond_re,
             morph_info,
         })
@@ -95,10 +99,15 @@ impl ParsedRule {
         } else {
             Some(strip.into())
         };
+        let (affix, cont_flags) = match affix.split_once('/') {
+            Some((afx, flags)) => (afx, Some(flags.to_owned())),
+            None => (affix, None),
+        };
 
         Ok(Self {
             strip: strip_chars,
             affix: affix.to_owned(),
+            cont_flags,
             condition: cond_re,
             morph_info,
         })
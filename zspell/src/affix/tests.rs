@@ -1,8 +1,62 @@
 //! Affix tests
 
 use super::*;
+use crate::error::BuildError;
 
 #[test]
 fn test_flagtype_convert_ok() {
     assert_eq!(FlagType::Ascii.str_to_flag("T"), Ok(Flag(84)));
 }
+
+#[test]
+fn test_conversion_apply_all() {
+    let table = [
+        Conversion::new("’", "'", false),
+        Conversion::new("a", "b", false),
+        Conversion::new("aa", "c", false),
+        Conversion::new("_x", "y", false),
+        Conversion::new("z_", "w_w", false),
+    ];
+
+    assert!(matches!(
+        Conversion::apply_all(&table, "don't"),
+        Cow::Borrowed("don't")
+    ));
+    assert_eq!(Conversion::apply_all(&table, "don’t"), "don't");
+    // Longest match wins
+    assert_eq!(Conversion::apply_all(&table, "aaa"), "cb");
+    // Anchors
+    assert_eq!(Conversion::apply_all(&table, "xax"), "ybx");
+    assert_eq!(Conversion::apply_all(&table, "zaz"), "zbw w");
+    assert_eq!(Conversion::apply_all(&[], "abc"), "abc");
+}
+
+#[test]
+fn test_encoding_sniff() {
+    assert_eq!(Encoding::sniff(b"FLAG long\n"), Ok(Encoding::Utf8));
+    assert_eq!(Encoding::sniff(b"# c\nSET KOI8-R\r\n"), Ok(Encoding::Koi8R));
+    assert_eq!(Encoding::sniff(b"  SET cp1251 # x\n"), Ok(Encoding::Cp1251));
+    // Not the `SET` directive
+    assert_eq!(Encoding::sniff(b"SETX UTF-8\n"), Ok(Encoding::Utf8));
+    assert_eq!(Encoding::sniff(b"SET foo\n"), Err(ParseErrorKind::Encoding));
+}
+
+#[test]
+fn test_encoding_decode() {
+    assert_eq!(
+        Encoding::Utf8.decode("\u{feff}мир".as_bytes()).unwrap(),
+        "мир"
+    );
+    assert_eq!(Encoding::Koi8R.decode(b"\xcd\xc9\xd2").unwrap(), "мир");
+    assert_eq!(Encoding::Cp1251.decode(b"\xec\xe8\xf0").unwrap(), "мир");
+    assert_eq!(Encoding::Iso8859t1.decode(b"caf\xe9").unwrap(), "café");
+    assert_eq!(Encoding::Iso8859t15.decode(b"\xe4").unwrap(), "ä");
+    assert_eq!(
+        Encoding::Utf8.decode(b"caf\xe9"),
+        Err(BuildError::Decode(Encoding::Utf8))
+    );
+    assert_eq!(
+        Encoding::IsciiDevanagari.decode(b"abc"),
+        Err(BuildError::UnsupportedEncoding(Encoding::IsciiDevanagari))
+    );
+}
@@ -159,6 +159,18 @@ fn test_afx_table_parser_err() {
     assert_eq!(res.unwrap_err().span().unwrap(), &Span::new(1, 0));
 }
 
+#[test]
+fn test_afx_table_parser_cont_flags() {
+    let s = "SFX A Y 2\nSFX A 0 s/BC .\nSFX A y ies y";
+    let Ok(Some((AffixNode::Suffix(group), "", 2))) = parse_suffix(s) else {
+        panic!("failed to parse {s}");
+    };
+    assert_eq!(group.rules[0].affix, "s");
+    assert_eq!(group.rules[0].cont_flags.as_deref(), Some("BC"));
+    assert_eq!(group.rules[1].affix, "ies");
+    assert_eq!(group.rules[1].cont_flags, None);
+}
+
 const SAMPLE_AFX_OK: &str = r#"
 SET UTF-8
 TRY abcd'
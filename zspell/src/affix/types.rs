@@ -1,5 +1,6 @@
 //! Type representations for affix file contents
 
+use std::borrow::Cow;
 use std::fmt::{self, Display};
 use std::str::FromStr;
 
@@ -7,7 +8,7 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::dict::Flag;
-use crate::error::ParseErrorKind;
+use crate::error::{BuildError, ParseErrorKind};
 use crate::morph::MorphStr;
 
 lazy_static! {
@@ -263,6 +264,61 @@ impl Conversion {
             bidirectional,
         })
     }
+
+    /// Return the pattern without anchors, and whether it is anchored to the start
+    /// and/or end of a word (indicated by a leading or trailing `_`)
+    fn pattern(&self) -> (&str, bool, bool) {
+        let (pat, start) = self
+            .input
+            .strip_prefix('_')
+            .map_or((self.input.as_str(), false), |p| (p, true));
+        let (pat, end) = pat.strip_suffix('_').map_or((pat, false), |p| (p, true));
+        (pat, start, end)
+    }
+
+    /// Apply a conversion table (`ICONV` or `OCONV`) to a string. At each position,
+    /// the longest matching pattern is replaced. Anchored patterns are preferred over
+    /// unanchored patterns of the same length.
+    pub(crate) fn apply_all<'a>(table: &[Self], s: &'a str) -> Cow<'a, str> {
+        if table.is_empty() {
+            return Cow::Borrowed(s);
+        }
+
+        let mut ret = String::new();
+        let mut changed = false;
+        let mut idx = 0;
+
+        while idx < s.len() {
+            let remaining = &s[idx..];
+            let best = table
+                .iter()
+                .filter_map(|conv| {
+                    let (pat, start, end) = conv.pattern();
+                    let matched = !pat.is_empty()
+                        && remaining.starts_with(pat)
+                        && (!start || idx == 0)
+                        && (!end || pat.len() == remaining.len());
+                    matched.then_some((pat.len(), usize::from(start) + usize::from(end), conv))
+                })
+                .max_by_key(|(len, anchors, _)| (*len, *anchors));
+
+            if let Some((len, _, conv)) = best {
+                ret.push_str(&conv.output.replace('_', " "));
+                idx += len;
+                changed = true;
+            } else {
+                let ch = remaining.chars().next().unwrap();
+                ret.push(ch);
+                idx += ch.len_utf8();
+            }
+        }
+
+        if changed {
+            Cow::Owned(ret)
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
 }
 
 /* Trait implementations */
@@ -286,6 +342,54 @@ impl TryFrom<&str> for Encoding {
     }
 }
 
+impl Encoding {
+    /// Locate the `SET` line in the raw bytes of an affix file and parse its encoding.
+    /// Returns the default (UTF-8) if there is no `SET` line.
+    pub(crate) fn sniff(bytes: &[u8]) -> Result<Self, ParseErrorKind> {
+        let set_line = bytes.split(|b| *b == b'\n').find_map(|line| {
+            let start = line.iter().position(|b| !b.is_ascii_whitespace())?;
+            let rest = line[start..].strip_prefix(b"SET")?;
+            rest.first()
+                .filter(|b| b.is_ascii_whitespace())
+                .map(|_| rest)
+        });
+
+        let Some(rest) = set_line else {
+            return Ok(Self::default());
+        };
+
+        // The encoding name is always ASCII, so anything else is an error
+        std::str::from_utf8(rest)
+            .ok()
+            .and_then(|s| s.split_whitespace().next())
+            .ok_or(ParseErrorKind::Encoding)
+            .and_then(Self::try_from)
+    }
+
+    /// Decode bytes in this encoding to a UTF-8 string
+    pub(crate) fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, BuildError> {
+        let decoder = match self {
+            Self::Utf8 => {
+                let s = std::str::from_utf8(bytes).map_err(|_| BuildError::Decode(self.clone()))?;
+                return Ok(Cow::Borrowed(s.strip_prefix('\u{feff}').unwrap_or(s)));
+            }
+            // Latin-1 bytes map directly to the first 256 code points
+            Self::Iso8859t1 => return Ok(bytes.iter().map(|b| char::from(*b)).collect()),
+            Self::Iso8859t10 => encoding_rs::ISO_8859_10,
+            Self::Iso8859t13 => encoding_rs::ISO_8859_13,
+            Self::Iso8859t15 => encoding_rs::ISO_8859_15,
+            Self::Koi8R => encoding_rs::KOI8_R,
+            Self::Koi8U => encoding_rs::KOI8_U,
+            Self::Cp1251 => encoding_rs::WINDOWS_1251,
+            Self::IsciiDevanagari => return Err(BuildError::UnsupportedEncoding(self.clone())),
+        };
+
+        decoder
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .ok_or_else(|| BuildError::Decode(self.clone()))
+    }
+}
+
 impl From<Encoding> for &str {
     #[inline]
     fn from(val: Encoding) -> Self {
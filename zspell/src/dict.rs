@@ -1,29 +1,42 @@
 //! Main datastructure module with entrypoints for checking
 
+mod bytes;
+mod cache;
+mod compact;
 mod flags;
 mod meta;
+mod multi;
+mod munch;
 mod parse;
 mod rule;
 mod rules_apply;
 mod rules_reverse;
+mod stack;
+mod unmunch;
 
+use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::fmt;
+use std::path::Path;
 use std::sync::Arc;
+use std::{fmt, fs};
 
 use hashbrown::{HashMap, HashSet};
 use stringmetrics::try_levenshtein;
-use unicode_segmentation::UnicodeSegmentation;
 use xxhash_rust::xxh32::xxh32;
 
+pub use self::compact::CompactDictionary;
 pub use self::flags::{Flag, FlagValue};
 use self::meta::{Meta, PersonalMeta, Source};
+pub use self::multi::{Misspelling, MultiDictionary};
 pub use self::parse::DictEntry;
 use self::parse::PersonalEntry;
 pub use self::rule::AfxRule;
-use self::rules_apply::{create_affixed_word_map, word_splitter};
-use crate::affix::{CompiledFlags, FlagType};
-use crate::error::{BuildError, Error};
+use self::rules_apply::{create_affixed_word_map, word_splitter, AfxContext};
+use self::rules_reverse::{LazyIndex, LazyStem, StemAttrs};
+pub use self::stack::{DictionaryStack, StackEntry};
+pub use self::unmunch::{FormKind, WordForm};
+use crate::affix::{CompiledFlags, Encoding, FlagType};
+use crate::error::{BuildError, Error, ParseError};
 use crate::helpers::StrWrapper;
 use crate::morph::MorphInfo;
 use crate::ParsedCfg;
@@ -58,17 +71,30 @@ pub struct Dictionary {
     wordlist_nosuggest: WordList,
     /// Words forbidden by the personal dictionary, i.e. do not accept as correct
     wordlist_forbidden: WordList,
+    /// Words that are rare or commonly confused (`WARN`)
+    warn: HashSet<Box<str>>,
+    /// Words that are accepted but never suggested or generated (`SUBSTANDARD`)
+    substandard: HashSet<Box<str>>,
 
     /* the following few types are used to store  meta information */
     /// A list of all stem words
     stems: HashSet<Arc<str>>,
+    /// Flags given to each stem, used when a personal word names it as a friend
+    stem_flags: HashMap<Arc<str>, Box<[Flag]>>,
+    /// Stems of dictionary entries that name a different stem with `st:`, keyed by
+    /// that stem. Used to generate irregular forms.
+    alt_stems: HashMap<Box<str>, Vec<Arc<str>>>,
     /// Flags and rules that apply to affixes
     affix_flags: BTreeMap<Flag, FlagValue>,
     /// Flags that apply to other flags or rules
+    rule_flags: BTreeMap<Flag, FlagValue>,
     /// Possible morphs
     morphs: HashSet<Arc<MorphInfo>>,
     /// Type of flags to expect in our file
     flag_type: FlagType,
+    /// Stems with their affix rules, if affixed words are checked by stripping affixes
+    /// rather than being expanded into the wordlists
+    lazy: Option<Box<LazyIndex>>,
     /// Affix configuration file. This will also hold references where our `meta`
     /// object points
     // FIXME: we don't need to store the whole `Config` here. It would be better
@@ -81,20 +107,25 @@ impl Dictionary {
     /// Create a new empty dictionary with default config
     #[inline]
     fn new(cfg: ParsedCfg) -> Result<Self, Error> {
-        // FIXME: what do we do with rule flags?
         let CompiledFlags {
             affix_flags,
-            rule_flags: _,
+            rule_flags,
         } = cfg.compile_flags()?;
 
         Ok(Self {
             wordlist: WordList::new(),
             wordlist_nosuggest: WordList::new(),
             wordlist_forbidden: WordList::new(),
+            warn: HashSet::new(),
+            substandard: HashSet::new(),
             stems: HashSet::new(),
+            stem_flags: HashMap::new(),
+            alt_stems: HashMap::new(),
             morphs: HashSet::new(),
             affix_flags,
+            rule_flags,
             flag_type: cfg.flag_type(),
+            lazy: None,
             parsed_config: Box::new(cfg),
         })
     }
@@ -122,7 +153,7 @@ impl Dictionary {
     /// ```
     #[inline]
     pub fn check(&self, input: &str) -> bool {
-        input.unicode_words().all(|w| self.check_word(w))
+        word_splitter(input, self.parsed_config.word_chars()).all(|(_, w)| self.check_word(w))
     }
 
     /// Check that a single word is spelled correctly, returns `true` if so
@@ -147,13 +178,39 @@ impl Dictionary {
     /// ```
     #[inline]
     pub fn check_word(&self, word: &str) -> bool {
+        self.check_word_inner(word)
+            || strip_period(word).map_or(false, |w| self.check_word_inner(w))
+    }
+
+    /// Check a single word without handling trailing periods
+    fn check_word_inner(&self, word: &str) -> bool {
         // FIXME: we should make sure there are no overlaps among our wordlists
+        let word = self.parsed_config.normalize_input(word);
+        let word = word.as_ref();
         let lower = word.to_lowercase();
-        (!self.wordlist_forbidden.0.contains_key(word))
-            && (self.wordlist.0.contains_key(word)
-                || self.wordlist.0.contains_key(lower.as_str())
-                || self.wordlist_nosuggest.0.contains_key(word)
-                || self.wordlist_nosuggest.0.contains_key(lower.as_str()))
+
+        // Same as `word_ctx`, a forbidden lowercase form rejects the word
+        if self.wordlist_forbidden.0.contains_key(word)
+            || self.wordlist_forbidden.0.contains_key(lower.as_str())
+        {
+            return false;
+        }
+
+        let found = |key: &str| {
+            self.wordlist.0.contains_key(key) || self.wordlist_nosuggest.0.contains_key(key)
+        };
+        let warn = if found(word) {
+            self.warn.contains(word)
+        } else if found(lower.as_str()) {
+            self.warn.contains(lower.as_str())
+        } else {
+            match self.lazy_ctx(word, &lower) {
+                Some(WordCtx::Correct { warn, .. }) => warn,
+                _ => return false,
+            }
+        };
+
+        !(self.parsed_config.forbid_warn_words() && warn)
     }
 
     /// Check words in a string, returning a list of the start and end indices
@@ -199,58 +256,122 @@ impl Dictionary {
         &'d self,
         input: &'a str,
     ) -> impl Iterator<Item = (usize, &'a str)> + 'd {
-        word_splitter(input).filter(|(_idx, w)| !self.check_word(w))
+        word_splitter(input, self.parsed_config.word_chars())
+            .filter(|(_idx, w)| !self.check_word(w))
     }
 
     /// Helper for `locate_word` that allows setting the index
     fn locate_word_inner<'d, 's>(&'d self, word: &'s str, index: usize) -> WordEntry<'d, 's> {
-        let lower = word.to_lowercase();
-
-        let ctx = if self.wordlist_forbidden.0.contains_key(word)
-            || self.wordlist_forbidden.0.contains_key(lower.as_str())
-        {
-            WordCtx::Incorrect { forbidden: true }
-        } else if let Some((matched, meta)) = self.wordlist.0.get_key_value(word) {
-            WordCtx::Correct {
-                matched,
-                meta_list: meta,
+        let mut ctx = self.word_ctx(word);
+        if matches!(ctx, WordCtx::Incorrect { forbidden: false }) {
+            if let Some(stripped) = strip_period(word) {
+                ctx = self.word_ctx(stripped);
             }
-        } else if let Some((matched, meta)) = self.wordlist.0.get_key_value(lower.as_str()) {
-            WordCtx::Correct {
-                matched,
-                meta_list: meta,
-            }
-        } else if let Some((matched, meta)) = self.wordlist_nosuggest.0.get_key_value(word) {
-            WordCtx::Correct {
-                matched,
-                meta_list: meta,
-            }
-        } else if let Some((matched, meta)) =
-            self.wordlist_nosuggest.0.get_key_value(lower.as_str())
-        {
+        }
+
+        let (warn, substandard) = match ctx {
             WordCtx::Correct {
-                matched,
-                meta_list: meta,
-            }
-        } else {
-            WordCtx::Incorrect { forbidden: false }
+                warn, substandard, ..
+            } => (warn, substandard),
+            WordCtx::Incorrect { .. } => (false, false),
         };
 
+        if warn && self.parsed_config.forbid_warn_words() {
+            ctx = WordCtx::Incorrect { forbidden: false };
+        }
+
         WordEntry {
             word,
             index,
             dict: self,
             context: ctx,
+            warn,
+            substandard,
+        }
+    }
+
+    /// Find a word in our wordlists without handling trailing periods
+    fn word_ctx(&self, word: &str) -> WordCtx<'_> {
+        let normalized = self.parsed_config.normalize_input(word);
+        let lookup = normalized.as_ref();
+        let lower = lookup.to_lowercase();
+
+        if self.wordlist_forbidden.0.contains_key(lookup)
+            || self.wordlist_forbidden.0.contains_key(lower.as_str())
+        {
+            WordCtx::Incorrect { forbidden: true }
+        } else if let Some(found) = self.wordlist.0.get_key_value(lookup) {
+            self.found_ctx(found.0, found.1)
+        } else if let Some(found) = self.wordlist.0.get_key_value(lower.as_str()) {
+            self.found_ctx(found.0, found.1)
+        } else if let Some(found) = self.wordlist_nosuggest.0.get_key_value(lookup) {
+            self.found_ctx(found.0, found.1)
+        } else if let Some(found) = self.wordlist_nosuggest.0.get_key_value(lower.as_str()) {
+            self.found_ctx(found.0, found.1)
+        } else {
+            self.lazy_ctx(lookup, &lower)
+                .unwrap_or(WordCtx::Incorrect { forbidden: false })
         }
     }
 
+    /// Context for a word found in a wordlist
+    fn found_ctx<'d>(&'d self, matched: &'d str, meta: &'d [Meta]) -> WordCtx<'d> {
+        WordCtx::Correct {
+            matched: Cow::Borrowed(matched),
+            meta_list: Cow::Borrowed(meta),
+            warn: self.warn.contains(matched),
+            substandard: self.substandard.contains(matched),
+        }
+    }
+
+    /// Look for a word by stripping affixes, if the dictionary was built with
+    /// [`Strategy::Lazy`]. Returns `None` if no match was found.
+    fn lazy_ctx(&self, lookup: &str, lower: &str) -> Option<WordCtx<'_>> {
+        let lazy = self.lazy.as_ref()?;
+        let ctx = AfxContext {
+            affix_flags: &self.affix_flags,
+            rule_flags: &self.rule_flags,
+            complex_prefixes: self.parsed_config.complex_prefixes(),
+        };
+
+        let exact = lazy.derive(lookup, &ctx);
+        let lowered = if lower == lookup {
+            Vec::new()
+        } else {
+            lazy.derive(lower, &ctx)
+        };
+
+        if exact.iter().chain(&lowered).any(|d| d.attrs.forbid) {
+            return Some(WordCtx::Incorrect { forbidden: true });
+        }
+
+        let (matched, found) = if exact.is_empty() {
+            (lower, lowered)
+        } else {
+            (lookup, exact)
+        };
+
+        if found.is_empty() {
+            return None;
+        }
+
+        Some(WordCtx::Correct {
+            matched: Cow::Owned(matched.to_owned()),
+            // A normal form of the same word takes precedence
+            warn: found.iter().all(|d| d.attrs.warn),
+            substandard: found.iter().all(|d| d.attrs.substandard),
+            meta_list: Cow::Owned(found.into_iter().flat_map(|d| d.metas).collect()),
+        })
+    }
+
     /// Return an iterator over entries for each word in a sentence.
     ///
     /// This can be used to stem or analyze words that are spelled correctly, or provide
     /// suggestions for incorrect words. See [`WordEntry`] for more information.
     #[inline]
     pub fn entries<'d, 's>(&'d self, input: &'s str) -> impl Iterator<Item = WordEntry<'d, 's>> {
-        word_splitter(input).map(|(idx, word)| self.locate_word_inner(word, idx))
+        word_splitter(input, self.parsed_config.word_chars())
+            .map(|(idx, word)| self.locate_word_inner(word, idx))
     }
 
     /// Return an entry for a single word.
@@ -262,6 +383,124 @@ impl Dictionary {
         self.locate_word_inner(word, 0)
     }
 
+    /// Iterate over every word form in the dictionary, like Hunspell's `unmunch`. This
+    /// includes stems and every word created from them by affix rules, along with
+    /// nosuggest, substandard and forbidden words; see [`WordForm::kind`].
+    ///
+    /// Words created from more than one stem are returned once for each stem. A word
+    /// that is forbidden is only returned as [`FormKind::Forbidden`], even if a valid
+    /// stem also creates it. The order is unspecified.
+    ///
+    /// ```
+    /// use zspell::{DictBuilder, FormKind};
+    ///
+    /// let affix_str = "
+    /// SFX S Y 1
+    /// SFX S 0 s .
+    /// ";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str("apple/S")
+    ///     .personal_str("*apples")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut forms: Vec<_> = dict
+    ///     .unmunch()
+    ///     .map(|form| (form.word().to_owned(), form.stem().to_owned(), form.kind()))
+    ///     .collect();
+    /// forms.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    ///
+    /// assert_eq!(
+    ///     forms,
+    ///     [
+    ///         ("apple".into(), "apple".into(), FormKind::Accepted),
+    ///         ("apples".into(), "apples".into(), FormKind::Forbidden),
+    ///     ]
+    /// );
+    /// ```
+    #[inline]
+    pub fn unmunch(&self) -> impl Iterator<Item = WordForm<'_>> {
+        unmunch::word_forms(self)
+    }
+
+    /// Generate the words created from a stem that have the given morphological
+    /// information, like Hunspell's `generate`. The stem may be a dictionary stem or the
+    /// stem given by an `st:` field, so irregular forms are found too.
+    ///
+    /// Every item in `morphs` must be present in a word's analysis, either from its
+    /// dictionary entry or from an applied affix rule. Forbidden and substandard words
+    /// are never generated. Words are returned sorted, with output conversions applied.
+    ///
+    /// ```
+    /// use zspell::{DictBuilder, MorphInfo};
+    ///
+    /// let affix_str = "
+    /// SFX D Y 1
+    /// SFX D 0 ed . is:past
+    ///
+    /// SFX G Y 1
+    /// SFX G 0 ing . is:gerund
+    /// ";
+    ///
+    /// let dict_str = "
+    /// 3
+    /// walk/DG po:verb
+    /// drink/G po:verb
+    /// drank st:drink po:verb is:past
+    /// ";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str(dict_str)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let past = MorphInfo::from("is:past");
+    /// assert_eq!(dict.generate("walk", &[past.clone()]), ["walked"]);
+    /// assert_eq!(dict.generate("drink", &[past]), ["drank"]);
+    /// assert_eq!(dict.generate("drink", &[]), ["drank", "drink", "drinking"]);
+    /// ```
+    #[inline]
+    pub fn generate(&self, stem: &str, morphs: &[MorphInfo]) -> Vec<Cow<'_, str>> {
+        unmunch::generate(self, stem, morphs)
+    }
+
+    /// Compress a plain list of words, one per line, into a dictionary file using this
+    /// dictionary's affix rules, like Hunspell's `munch`. This is the reverse of
+    /// [`unmunch`](Self::unmunch).
+    ///
+    /// Stems are chosen from the list and given every flag whose words are all in the
+    /// list, so the result creates exactly the given words. A small set of stems that
+    /// covers every word is then selected. Words already in this dictionary are not
+    /// taken into account, only its affix rules are used.
+    ///
+    /// Input conversions (`ICONV`) and `IGNORE` are applied to each word first. Slashes
+    /// in stems are written as `\/`. If the affix file defines `AF` aliases, each stem is
+    /// given the alias that creates the most words, written as its index.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "
+    /// PFX U Y 1
+    /// PFX U 0 un .
+    ///
+    /// SFX S Y 1
+    /// SFX S 0 s .
+    /// ";
+    ///
+    /// let dict = DictBuilder::new().config_str(affix_str).build().unwrap();
+    ///
+    /// let words = "lock\nlocks\nunlock\nunlocks\nbanana\nbananas\ncherry";
+    /// assert_eq!(dict.munch(words), "3\nbanana/S\ncherry\nlock/SU\n");
+    /// ```
+    #[inline]
+    pub fn munch(&self, words: &str) -> String {
+        munch::munch(self, words)
+    }
+
     /// Return a reference to the internal wordlist
     #[inline]
     #[doc(hidden)]
@@ -284,6 +523,320 @@ impl Dictionary {
     }
 }
 
+// Update API
+impl Dictionary {
+    /// Add a word to the dictionary, taking effect immediately. This is the same as
+    /// adding it to a personal dictionary.
+    ///
+    /// If the word was forbidden, it is allowed again.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str("")
+    ///     .dict_str("apple")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(!dict.check_word("zspell"));
+    /// dict.add_word("zspell");
+    /// assert!(dict.check_word("zspell"));
+    /// ```
+    #[inline]
+    pub fn add_word(&mut self, word: &str) {
+        self.unforbid(word);
+        self.insert_personal(word, None, &[], false);
+    }
+
+    /// Add a stem with flags, as they would be written in a dictionary file. All forms
+    /// created from the flags' affix rules are added too.
+    ///
+    /// If the stem was forbidden, it is allowed again.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "
+    /// SFX S Y 1
+    /// SFX S 0 s .
+    /// ";
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str("apple/S")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// dict.add_word_with_flags("banana", "S").unwrap();
+    /// assert!(dict.check("banana bananas"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the flags cannot be parsed with the dictionary's flag type.
+    #[inline]
+    pub fn add_word_with_flags(&mut self, stem: &str, flags: &str) -> Result<(), Error> {
+        let flags = self
+            .parsed_config
+            .parse_flags(flags)
+            .map_err(|e| ParseError::new_nospan(e, flags))?;
+
+        self.unforbid(stem);
+        self.create_affixed_words(stem, &flags, &[]);
+        Ok(())
+    }
+
+    /// Add a word that uses the same flags as an existing stem (its "friend" or affix
+    /// model), so it accepts the same affixes. This is the same as adding `word/model` to
+    /// a personal dictionary.
+    ///
+    /// Returns `false` if `model` is not a stem with flags. The word is still added, but
+    /// without affixes.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "
+    /// SFX S Y 1
+    /// SFX S 0 's .
+    /// ";
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str("Kant/S")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(dict.add_word_with_affix("Kafka", "Kant"));
+    /// assert!(dict.check("Kafka Kafka's"));
+    /// ```
+    #[inline]
+    pub fn add_word_with_affix(&mut self, word: &str, model: &str) -> bool {
+        self.unforbid(word);
+        self.insert_personal(word, Some(model), &[], false)
+    }
+
+    /// Remove a stem from the dictionary, along with all words created from it. Returns
+    /// `true` if anything was removed.
+    ///
+    /// Forbidden entries for the stem are removed too. This does not forbid the word,
+    /// it may still be accepted if it is also created from a different stem or (with
+    /// [`Strategy::Lazy`]) by stripping affixes. Use
+    /// [`forbid_word`](Self::forbid_word) to reject it completely.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "
+    /// SFX S Y 1
+    /// SFX S 0 s .
+    /// ";
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str("apple/S\nbanana/S")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(dict.remove_word("apple"));
+    /// assert!(!dict.check_word("apple"));
+    /// assert!(!dict.check_word("apples"));
+    /// assert!(dict.check_word("bananas"));
+    ///
+    /// // "bananas" is still created from "banana"
+    /// assert!(!dict.remove_word("bananas"));
+    /// assert!(dict.check_word("bananas"));
+    /// ```
+    #[inline]
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let word = self.parsed_config.strip_ignored(word);
+        let word = word.as_ref();
+        let mut removed_words = Vec::new();
+        let mut changed_words = Vec::new();
+        let mut removed = self.lazy.as_mut().map_or(false, |lazy| lazy.remove(word));
+
+        for wordlist in [
+            &mut self.wordlist,
+            &mut self.wordlist_nosuggest,
+            &mut self.wordlist_forbidden,
+        ] {
+            wordlist.0.retain(|key, metas| {
+                // Forms that are also created from other stems keep those metas
+                let count = metas.len();
+                metas.retain(|meta| meta.stem_rc().as_ref() != word);
+
+                if metas.is_empty() {
+                    removed_words.push(key.clone());
+                    false
+                } else {
+                    if metas.len() != count {
+                        changed_words.push(key.clone());
+                    }
+                    true
+                }
+            });
+        }
+
+        self.stem_flags.remove(word);
+        for key in removed_words.iter().chain(&changed_words) {
+            self.retrack_derivations(key);
+        }
+
+        for stems in self.alt_stems.values_mut() {
+            stems.retain(|stem| stem.as_ref() != word);
+        }
+        self.alt_stems.retain(|_, stems| !stems.is_empty());
+        removed |= !removed_words.is_empty();
+        removed |= self.stems.remove(word);
+        removed
+    }
+
+    /// Forbid a word so that it is never accepted, even if it is created from a valid
+    /// stem. This is the same as adding `*word` to a personal dictionary.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str("")
+    ///     .dict_str("apple\nbanana")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// dict.forbid_word("apple");
+    /// assert!(!dict.check_word("apple"));
+    /// assert!(dict.entry("apple").forbidden());
+    ///
+    /// // Adding the word again allows it
+    /// dict.add_word("apple");
+    /// assert!(dict.check_word("apple"));
+    /// ```
+    #[inline]
+    pub fn forbid_word(&mut self, word: &str) {
+        self.insert_personal(word, None, &[], true);
+    }
+
+    /// Add entries from a personal dictionary file to this dictionary. The format is the
+    /// same as for [`DictBuilder::personal_str`].
+    ///
+    /// # Errors
+    ///
+    /// Currently infallible, errors are reserved for invalid entries.
+    #[inline]
+    pub fn add_personal_str(&mut self, personal: &str) -> Result<(), Error> {
+        self.parse_update_personal(personal)
+    }
+
+    /// Add a plain list of words, one per line, to this dictionary. Each line is accepted
+    /// exactly as written, without affixes; blank lines are skipped. The format is the
+    /// same as for [`DictBuilder::word_list_str`].
+    ///
+    /// Words that are forbidden stay forbidden. Use [`add_word`](Self::add_word) to
+    /// allow them again.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str("")
+    ///     .dict_str("apple")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// dict.add_word_list("Vec\nHashMap\n");
+    /// assert!(dict.check("apple Vec HashMap"));
+    /// ```
+    #[inline]
+    pub fn add_word_list(&mut self, words: &str) {
+        for word in words.lines().map(str::trim).filter(|w| !w.is_empty()) {
+            let stem = self.intern_stem(word);
+            let meta = Meta::new(Arc::clone(&stem), Source::Raw);
+            self.track_derivation(&stem, false, false);
+            self.wordlist
+                .0
+                .entry_ref(stem.as_ref())
+                .or_insert_with(|| Vec::with_capacity(1))
+                .push(meta);
+        }
+    }
+
+    /// Write all personal words to a string in Hunspell's personal dictionary format.
+    /// This includes words from [`DictBuilder::personal_str`] and those added with
+    /// [`add_word`](Self::add_word), [`add_word_with_affix`](Self::add_word_with_affix)
+    /// and [`forbid_word`](Self::forbid_word).
+    ///
+    /// Each line has a word, followed by `/friend` if it has one and then any
+    /// morphological information. Forbidden words start with `*`, and a `/` within a word
+    /// is written as `\/`. Lines are sorted, and the result can be loaded again as a
+    /// personal dictionary.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let mut dict = DictBuilder::new()
+    ///     .config_str("SFX S Y 1\nSFX S 0 's .")
+    ///     .dict_str("Kant/S")
+    ///     .personal_str("zspell po:noun")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// dict.add_word_with_affix("Kafka", "Kant");
+    /// dict.forbid_word("teh");
+    ///
+    /// assert_eq!(dict.to_personal_string(), "*teh\nKafka/Kant\nzspell po:noun\n");
+    /// ```
+    #[inline]
+    pub fn to_personal_string(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+
+        for (wordlist, prefix) in [
+            (&self.wordlist, ""),
+            (&self.wordlist_nosuggest, ""),
+            (&self.wordlist_forbidden, "*"),
+        ] {
+            for (word, metas) in &wordlist.0 {
+                for meta in metas {
+                    let Source::Personal(personal) = meta.source() else {
+                        continue;
+                    };
+                    // Affixed forms of words with a friend come from the stem's line
+                    if meta.stem() != word.as_ref() {
+                        continue;
+                    }
+
+                    let mut line = format!("{prefix}{}", word.replace('/', "\\/"));
+                    if let Some(friend) = personal.friend() {
+                        line.push('/');
+                        line.push_str(&friend.replace('/', "\\/"));
+                    }
+                    for morph in personal.morph() {
+                        line.push(' ');
+                        line.push_str(&morph.to_string());
+                    }
+                    lines.push(line);
+                }
+            }
+        }
+
+        lines.sort_unstable();
+        lines.dedup();
+
+        let mut ret = String::new();
+        for line in lines {
+            ret.push_str(&line);
+            ret.push('\n');
+        }
+        ret
+    }
+
+    /// Remove a word from the forbidden wordlist
+    fn unforbid(&mut self, word: &str) {
+        let word = self.parsed_config.strip_ignored(word);
+        self.wordlist_forbidden.0.remove(word.as_ref());
+    }
+}
+
 /// Internal config API
 impl Dictionary {
     /// Create a vector of words from a single root word by applying rules in
@@ -291,73 +844,235 @@ impl Dictionary {
     ///
     /// May contain duplicates, does not contain the original word
     fn create_affixed_words(&mut self, stem: &str, flags: &[Flag], morph: &[Arc<MorphInfo>]) {
-        let mut prefix_rules = Vec::new();
-        let mut suffix_rules = Vec::new();
+        let stem = self.intern_stem(stem);
+        self.index_alt_stem(&stem, morph);
+        let expanded = self.expand_entry(&stem, flags, Self::dict_source(morph));
+        self.store_expanded(expanded);
+        self.store_flags(&stem, flags);
+    }
 
-        let stem: &Arc<str> = self
-            .stems
-            .get_or_insert_with(&StrWrapper::new(stem), |s: &StrWrapper| Arc::from(s.0));
+    /// Source for a stem from the dictionary file
+    fn dict_source(morph: &[Arc<MorphInfo>]) -> Source {
+        #[cfg(not(box_from_slice_has_clone_bound))]
+        let morph = morph.to_owned(); // create a temporary vec if < 1.71
+        Source::Dict(morph.into())
+    }
 
-        let mut add_stem = true;
-        let mut forbid = false;
-        let mut nosuggest = false;
+    /// Remember a stem's flags so other words can inherit them. Homonyms combine
+    /// their flags.
+    fn store_flags(&mut self, stem: &Arc<str>, flags: &[Flag]) {
+        if flags.is_empty() {
+            return;
+        }
 
-        for flag in flags {
-            if !self.affix_flags.contains_key(flag) {
-                // FIXME: we get stuck on compound rules
-                continue;
+        let entry = self
+            .stem_flags
+            .entry(Arc::clone(stem))
+            .or_insert_with(|| flags.into());
+        if !flags.iter().all(|flag| entry.contains(flag)) {
+            let mut combined = entry.to_vec();
+            combined.extend(flags.iter().filter(|flag| !entry.contains(flag)));
+            *entry = combined.into();
+        }
+    }
+
+    /// Remember a dictionary entry that names a different stem with `st:`
+    fn index_alt_stem(&mut self, stem: &Arc<str>, morph: &[Arc<MorphInfo>]) {
+        let alt = morph.iter().find_map(|m| match m.as_ref() {
+            MorphInfo::Stem(st) => Some(st.as_ref()),
+            _ => None,
+        });
+        let Some(alt) = alt.filter(|alt| *alt != stem.as_ref()) else {
+            return;
+        };
+
+        let stems = self.alt_stems.entry_ref(alt).or_default();
+        if !stems.contains(stem) {
+            stems.push(Arc::clone(stem));
+        }
+    }
+
+    /// Remove ignored characters from a stem and add it to our stem list
+    fn intern_stem(&mut self, stem: &str) -> Arc<str> {
+        let stem = self.parsed_config.strip_ignored(stem);
+        Arc::clone(
+            self.stems
+                .get_or_insert_with(&StrWrapper::new(&stem), |s: &StrWrapper| Arc::from(s.0)),
+        )
+    }
+
+    /// Create all words for a single entry without modifying the dictionary, so this
+    /// can run on multiple threads. Use [`store_expanded`](Self::store_expanded) to add
+    /// the result to our wordlists.
+    ///
+    /// `stem_source` is the source of the stem itself, usually the dictionary file.
+    fn expand_entry(&self, stem: &Arc<str>, flags: &[Flag], stem_source: Source) -> Expanded {
+        let EntryRules {
+            prefix_rules,
+            suffix_rules,
+            add_stem,
+            attrs,
+        } = EntryRules::new(&self.affix_flags, flags);
+
+        let mut ret = Expanded {
+            attrs,
+            words: WordList::new(),
+            substandard_words: WordList::new(),
+            lazy: None,
+        };
+
+        let dict_meta = if add_stem {
+            let meta = Meta::new(stem.clone(), stem_source);
+            ret.words
+                .0
+                .insert(stem.as_ref().into(), vec![Meta::clone(&meta)]);
+            Some(meta)
+        } else {
+            None
+        };
+
+        if self.lazy.is_some() {
+            // Affixed words are found by stripping affixes when checking
+            if !prefix_rules.is_empty() || !suffix_rules.is_empty() {
+                let entry = LazyStem {
+                    rules: prefix_rules
+                        .iter()
+                        .chain(&suffix_rules)
+                        .map(|r| Arc::clone(r))
+                        .collect(),
+                    dict_meta,
+                    attrs,
+                };
+                ret.lazy = Some((Arc::clone(stem), entry));
             }
+        } else {
+            let ctx = AfxContext {
+                affix_flags: &self.affix_flags,
+                rule_flags: &self.rule_flags,
+                complex_prefixes: self.parsed_config.complex_prefixes(),
+            };
+
+            create_affixed_word_map(
+                stem,
+                &prefix_rules,
+                &suffix_rules,
+                &ctx,
+                dict_meta.as_ref(),
+                &mut ret.words,
+                &mut ret.substandard_words,
+            );
+        }
 
-            match self.affix_flags.get(flag).unwrap() {
-                FlagValue::ForbiddenWord => forbid = true,
-                FlagValue::NoSuggest => nosuggest = true,
-                FlagValue::Rule(rule) => {
-                    if rule.is_pfx() {
-                        prefix_rules.push(rule);
-                    } else {
-                        suffix_rules.push(rule);
-                    }
-                }
-                // Don't add the stem to the dictionary
-                FlagValue::AfxNeeded => add_stem = false,
-                _ => {
-                    // FIXME: should be unimplemented
-                    // unimplemented!()
-                    // eprintln!("unexpected flag {}", flag);
-                }
+        ret
+    }
+
+    /// Add words created by [`expand_entry`](Self::expand_entry) to our wordlists
+    fn store_expanded(&mut self, expanded: Expanded) {
+        let Expanded {
+            attrs,
+            words,
+            substandard_words,
+            lazy,
+        } = expanded;
+
+        if let Some(((stem, entry), index)) = lazy.zip(self.lazy.as_mut()) {
+            index.insert(&stem, entry);
+        }
+
+        let words = words.0.into_iter().map(|(w, m)| (w, m, false));
+        let from_substandard = substandard_words.0.into_iter().map(|(w, m)| (w, m, true));
+
+        for (word, metas, substandard_afx) in words.chain(from_substandard) {
+            self.track_derivation(&word, attrs.warn, attrs.substandard || substandard_afx);
+
+            // Forbid trumps nosuggest
+            let dest = if attrs.forbid {
+                &mut self.wordlist_forbidden
+            } else if attrs.nosuggest {
+                &mut self.wordlist_nosuggest
+            } else {
+                &mut self.wordlist
+            };
+            dest.0.entry(word).or_default().extend(metas);
+        }
+    }
+
+    /// Keep track of words where every way of creating them is flagged `WARN` or is
+    /// substandard, so the result does not depend on the order of entries. Must be called
+    /// before the derivation's metas are added to a wordlist.
+    fn track_derivation(&mut self, word: &str, warn: bool, substandard: bool) {
+        let is_new = !(self.wordlist.0.contains_key(word)
+            || self.wordlist_nosuggest.0.contains_key(word)
+            || self.wordlist_forbidden.0.contains_key(word));
+
+        for (set, flagged) in [(&mut self.warn, warn), (&mut self.substandard, substandard)] {
+            if !flagged {
+                set.remove(word);
+            } else if is_new {
+                set.insert(word.into());
             }
         }
+    }
 
-        // Forbid trumps nosuggest
-        let dest = if forbid {
-            &mut self.wordlist_forbidden
-        } else if nosuggest {
-            &mut self.wordlist_nosuggest
-        } else {
-            &mut self.wordlist
+    /// Recompute whether every remaining way of creating a word is `WARN` or
+    /// substandard, after some of its derivations were removed
+    fn retrack_derivations(&mut self, word: &str) {
+        let lists = [
+            &self.wordlist,
+            &self.wordlist_nosuggest,
+            &self.wordlist_forbidden,
+        ];
+        let ctx = AfxContext {
+            affix_flags: &self.affix_flags,
+            rule_flags: &self.rule_flags,
+            complex_prefixes: self.parsed_config.complex_prefixes(),
         };
 
-        let dict_meta = if add_stem {
-            #[cfg(not(box_from_slice_has_clone_bound))]
-            let morph = morph.to_owned(); // create a temporary vec if < 1.71
-            let meta = Meta::new(stem.clone(), Source::Dict(morph.into()));
-            let meta_vec = dest.0.entry_ref(stem.as_ref()).or_insert_with(Vec::new);
-            meta_vec.push(Meta::clone(&meta));
-            Some(meta)
-        } else {
-            None
-        };
+        let mut found = false;
+        let (mut warn, mut substandard) = (true, true);
+        for metas in lists.into_iter().filter_map(|list| list.0.get(word)) {
+            for derivation in split_derivations(metas) {
+                let flags = self
+                    .stem_flags
+                    .get(derivation[0].stem_rc())
+                    .map_or(&[][..], |flags| flags);
+                let attrs = EntryRules::new(&self.affix_flags, flags).attrs;
+                let applied: Vec<_> = derivation
+                    .iter()
+                    .filter_map(|meta| match meta.source() {
+                        Source::Affix { rule, pat_idx } => Some((rule, *pat_idx)),
+                        _ => None,
+                    })
+                    .collect();
+
+                found = true;
+                warn &= attrs.warn;
+                substandard &= attrs.substandard || ctx.is_substandard(&applied);
+            }
+        }
 
-        create_affixed_word_map(stem, &prefix_rules, &suffix_rules, dict_meta.as_ref(), dest);
-        prefix_rules.clear();
-        suffix_rules.clear();
+        for (set, flagged) in [(&mut self.warn, warn), (&mut self.substandard, substandard)] {
+            if found && flagged {
+                set.insert(word.into());
+            } else {
+                set.remove(word);
+            }
+        }
     }
 
     /// Update the internal wordlist and forbidden wordlist from a dictionary
     /// file string
-    fn parse_update_wordlist(&mut self, source: &str) -> Result<(), Error> {
+    fn parse_update_wordlist(&mut self, source: &str, parallel: bool) -> Result<(), Error> {
         // FIXME: this could potentially be lazy, I don't think we need to collect to a Vec
-        let entries = DictEntry::parse_all(source, self.flag_type)?;
+        #[cfg(feature = "parallel")]
+        if parallel {
+            let entries = DictEntry::parse_all_par(source, &self.parsed_config)?;
+            self.update_wordlist_par(&entries);
+            return Ok(());
+        }
+
+        let _ = parallel;
+        let entries = DictEntry::parse_all(source, &self.parsed_config)?;
         self.update_wordlist(&entries);
         Ok(())
     }
@@ -375,41 +1090,101 @@ impl Dictionary {
         }
     }
 
+    /// Same as [`update_wordlist`](Self::update_wordlist) but expands entries on
+    /// multiple threads. Results are merged in order, so the dictionary is identical.
+    #[cfg(feature = "parallel")]
+    fn update_wordlist_par(&mut self, entries: &[DictEntry]) {
+        use rayon::prelude::*;
+
+        self.wordlist.0.reserve(entries.len() * 5);
+
+        let stems: Vec<Arc<str>> = entries.iter().map(|e| self.intern_stem(&e.stem)).collect();
+        let this = &*self;
+        let expanded: Vec<Expanded> = entries
+            .par_iter()
+            .zip(&stems)
+            .map(|(entry, stem)| {
+                this.expand_entry(stem, &entry.flags, Self::dict_source(&entry.morph))
+            })
+            .collect();
+
+        for ((item, stem), entry) in expanded.into_iter().zip(&stems).zip(entries) {
+            self.index_alt_stem(stem, &entry.morph);
+            self.store_expanded(item);
+            self.store_flags(stem, &entry.flags);
+        }
+    }
+
     #[allow(clippy::unnecessary_wraps)] // parsing may become fallible
-    fn parse_update_personal(&mut self, source: &str, dict: &[DictEntry]) -> Result<(), Error> {
+    fn parse_update_personal(&mut self, source: &str) -> Result<(), Error> {
         let entries = PersonalEntry::parse_all(source);
-        self.update_personal(entries, dict);
+        self.update_personal(entries);
         Ok(())
     }
 
     /// Must happen after `update_wordlist`
-    fn update_personal(&mut self, entries: Vec<PersonalEntry>, _dict: &[DictEntry]) {
-        // FIXME: don't take `dict` as an argument, use our existing hashmaps
+    fn update_personal(&mut self, entries: Vec<PersonalEntry>) {
         self.wordlist.0.reserve(entries.len() * 2);
         for entry in entries {
-            if let Some(_friend) = &entry.friend {
-                // FIXME:friends Find the friend in our dictionary, find its source affixes
-                // let flags = dict.iter().find(|d| &d.stem() == friend).map(|d| &d.flags);
-            } else {
-                let stem_arc: Arc<str> = self.stems.get_or_insert(entry.stem).clone();
-                let meta = PersonalMeta::new(None, self.get_or_insert_morphs(&entry.morph));
-                let source = Source::Personal(Arc::new(meta));
-                let meta = Meta::new(Arc::clone(&stem_arc), source);
-
-                // Select the correct word to work with
-                let hmap = if entry.forbid {
-                    &mut self.wordlist_forbidden.0
-                } else {
-                    &mut self.wordlist.0
-                };
+            self.insert_personal(
+                &entry.stem,
+                entry.friend.as_deref(),
+                &entry.morph,
+                entry.forbid,
+            );
+        }
+    }
 
-                // Add our word, update its meta
-                let extra_vec: &mut Vec<Meta> = hmap
-                    .entry_ref(stem_arc.as_ref())
-                    .or_insert_with(|| Vec::with_capacity(1));
-                extra_vec.push(meta);
-            }
+    /// Add a word with a personal dictionary source to the main or forbidden wordlist.
+    ///
+    /// If a friend is given, the word inherits the friend stem's flags so its affixed
+    /// forms are accepted too. Returns `false` if the friend has no known flags.
+    fn insert_personal(
+        &mut self,
+        stem: &str,
+        friend: Option<&str>,
+        morph: &[MorphInfo],
+        forbid: bool,
+    ) -> bool {
+        let stem_arc = self.intern_stem(stem);
+        let friend = friend.map(|f| self.parsed_config.strip_ignored(f));
+        let friend_flags = friend
+            .as_ref()
+            .and_then(|f| self.stem_flags.get(f.as_ref()))
+            .cloned();
+
+        let meta = PersonalMeta::new(
+            friend.map(|f| f.as_ref().into()),
+            self.get_or_insert_morphs(morph),
+        );
+        let source = Source::Personal(Arc::new(meta));
+
+        if let (Some(flags), false) = (&friend_flags, forbid) {
+            let expanded = self.expand_entry(&stem_arc, flags, source);
+            self.store_expanded(expanded);
+            self.store_flags(&stem_arc, flags);
+            return true;
+        }
+
+        let meta = Meta::new(Arc::clone(&stem_arc), source);
+        if !forbid {
+            self.track_derivation(&stem_arc, false, false);
         }
+
+        // Select the correct word to work with
+        let hmap = if forbid {
+            &mut self.wordlist_forbidden.0
+        } else {
+            &mut self.wordlist.0
+        };
+
+        // Add our word, update its meta
+        let extra_vec: &mut Vec<Meta> = hmap
+            .entry_ref(stem_arc.as_ref())
+            .or_insert_with(|| Vec::with_capacity(1));
+        extra_vec.push(meta);
+
+        friend_flags.is_some()
     }
 
     /// For each morph in the slice: find it or insert it in our hashset, return
@@ -431,9 +1206,116 @@ impl Dictionary {
         self.wordlist.0.shrink_to_fit();
         self.wordlist_nosuggest.0.shrink_to_fit();
         self.wordlist_forbidden.0.shrink_to_fit();
+        self.warn.shrink_to_fit();
+        self.substandard.shrink_to_fit();
         self.stems.shrink_to_fit();
+        self.stem_flags.shrink_to_fit();
+        self.alt_stems.shrink_to_fit();
         self.morphs.shrink_to_fit();
+        if let Some(lazy) = self.lazy.as_mut() {
+            lazy.shrink_to_fit();
+        }
+    }
+}
+
+/// The rules and attributes given by a dictionary entry's flags
+struct EntryRules<'a> {
+    prefix_rules: Vec<&'a Arc<AfxRule>>,
+    suffix_rules: Vec<&'a Arc<AfxRule>>,
+    /// False if the stem itself is not a word (`NEEDAFFIX`)
+    add_stem: bool,
+    attrs: StemAttrs,
+}
+
+impl<'a> EntryRules<'a> {
+    fn new(affix_flags: &'a BTreeMap<Flag, FlagValue>, flags: &[Flag]) -> Self {
+        let mut ret = Self {
+            prefix_rules: Vec::new(),
+            suffix_rules: Vec::new(),
+            add_stem: true,
+            attrs: StemAttrs::default(),
+        };
+
+        for flag in flags {
+            let Some(value) = affix_flags.get(flag) else {
+                // FIXME: we get stuck on compound rules
+                continue;
+            };
+
+            match value {
+                FlagValue::ForbiddenWord => ret.attrs.forbid = true,
+                FlagValue::NoSuggest => ret.attrs.nosuggest = true,
+                FlagValue::Rule(rule) => {
+                    if rule.is_pfx() {
+                        ret.prefix_rules.push(rule);
+                    } else {
+                        ret.suffix_rules.push(rule);
+                    }
+                }
+                // Don't add the stem to the dictionary
+                FlagValue::AfxNeeded => ret.add_stem = false,
+                FlagValue::WarnRare => ret.attrs.warn = true,
+                FlagValue::AfxSubstandard => ret.attrs.substandard = true,
+                _ => {
+                    // FIXME: should be unimplemented
+                    // unimplemented!()
+                    // eprintln!("unexpected flag {}", flag);
+                }
+            }
+        }
+
+        ret
+    }
+}
+
+/// Words created from a single dictionary entry, before they are added to a [`Dictionary`]
+struct Expanded {
+    /// Attributes from the entry's flags
+    attrs: StemAttrs,
+    /// The stem (unless `NEEDAFFIX` is set) and all affixed words
+    words: WordList,
+    /// Words created with a `SUBSTANDARD` affix
+    substandard_words: WordList,
+    /// Stem and rules to add to the index with [`Strategy::Lazy`]
+    lazy: Option<(Arc<str>, LazyStem)>,
+}
+
+/// Combine the main stem of each meta with every stem provided by its morphs
+fn meta_stems(meta_list: &[Meta]) -> impl Iterator<Item = &str> {
+    meta_list.iter().flat_map(|meta| {
+        let stem = std::iter::once(meta.stem());
+        let morph_stems = meta.source().morphs().filter_map(|morph| match morph {
+            MorphInfo::Stem(v) => Some(v.as_ref()),
+            _ => None,
+        });
+
+        stem.chain(morph_stems)
+    })
+}
+
+/// Split a word's metadata into the metas for each way it was created. Each derivation
+/// is a run of affix metas from the same stem, ending with the entry's own meta.
+fn split_derivations(meta_list: &[Meta]) -> Vec<&[Meta]> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+
+    for (idx, meta) in meta_list.iter().enumerate() {
+        let next_stem_differs = meta_list
+            .get(idx + 1)
+            .map_or(true, |next| next.stem_rc() != meta.stem_rc());
+        if next_stem_differs || !matches!(meta.source(), Source::Affix { .. }) {
+            ret.push(&meta_list[start..=idx]);
+            start = idx + 1;
+        }
     }
+
+    ret
+}
+
+/// If `.` is a word character, words at the end of a sentence will include a period. Return
+/// the word without it so it can be checked on its own.
+fn strip_period(word: &str) -> Option<&str> {
+    word.strip_suffix('.').filter(|w| !w.is_empty())
 }
 
 /// The result of checking whether a word exists or not, with methods to perform
@@ -447,6 +1329,10 @@ pub struct WordEntry<'dict, 'word> {
     index: usize,
     dict: &'dict Dictionary,
     context: WordCtx<'dict>,
+    /// The matched word is flagged as rare or commonly confused
+    warn: bool,
+    /// The matched word is a substandard form
+    substandard: bool,
 }
 
 impl<'dict, 'word> fmt::Debug for WordEntry<'dict, 'word> {
@@ -457,6 +1343,8 @@ impl<'dict, 'word> fmt::Debug for WordEntry<'dict, 'word> {
             .field("word", &self.word)
             .field("index", &self.index)
             .field("context", &self.context)
+            .field("warn", &self.warn)
+            .field("substandard", &self.substandard)
             .finish()
     }
 }
@@ -467,9 +1355,13 @@ impl<'dict, 'word> fmt::Debug for WordEntry<'dict, 'word> {
 enum WordCtx<'dict> {
     Correct {
         /// The value that was matched in the dictionary
-        matched: &'dict str,
-        /// Meta located in the dictionary
-        meta_list: &'dict [Meta],
+        matched: Cow<'dict, str>,
+        /// Meta located in the dictionary, or created by stripping affixes
+        meta_list: Cow<'dict, [Meta]>,
+        /// The matched word is flagged as rare or commonly confused
+        warn: bool,
+        /// The matched word is a substandard form
+        substandard: bool,
     },
     Incorrect {
         /// True if the word was located in a forbidden dictionary
@@ -477,7 +1369,7 @@ enum WordCtx<'dict> {
     },
 }
 
-impl<'dict, 'word> WordEntry<'dict, 'word> {
+impl<'dict> WordEntry<'dict, '_> {
     /// Return true if the word is spelled correctly.
     ///
     /// If you only need correctness checking, it can be easier to go through
@@ -514,7 +1406,7 @@ impl<'dict, 'word> WordEntry<'dict, 'word> {
     /// the same as `input` but not always).
     #[inline]
     pub fn matched_entry(&self) -> Option<&str> {
-        match self.context {
+        match &self.context {
             WordCtx::Correct { matched, .. } => Some(matched),
             WordCtx::Incorrect { .. } => None,
         }
@@ -526,11 +1418,77 @@ impl<'dict, 'word> WordEntry<'dict, 'word> {
         matches!(self.context, WordCtx::Incorrect { forbidden: true })
     }
 
+    /// True if this word is flagged with `WARN`, meaning it exists but is rare or
+    /// commonly confused with another word (e.g. "whit" for "with").
+    ///
+    /// If the dictionary specifies `FORBIDWARN`, these words are not accepted as correct.
+    /// They are not [`forbidden`](Self::forbidden); this flag is the reason they are
+    /// rejected. A word is only flagged if every stem that creates it has `WARN`.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "WARN W";
+    /// let dict_str = "
+    /// with
+    /// whit/W
+    /// ";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str(dict_str)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(!dict.entry("with").warn());
+    /// assert!(dict.entry("whit").warn());
+    /// assert!(dict.entry("whit").correct());
+    /// ```
+    #[inline]
+    pub fn warn(&self) -> bool {
+        self.warn
+    }
+
+    /// True if this word is a substandard form, from a stem or affix flagged with
+    /// `SUBSTANDARD`.
+    ///
+    /// Substandard words are accepted as correct, but they are never suggested.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "
+    /// SUBSTANDARD S
+    ///
+    /// SFX A Y 2
+    /// SFX A 0 ed .
+    /// SFX A 0 t/S .
+    /// ";
+    /// let dict_str = "
+    /// burn/A
+    /// ";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str(dict_str)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(!dict.entry("burned").substandard());
+    /// assert!(dict.entry("burnt").substandard());
+    /// assert!(dict.entry("burnt").correct());
+    /// ```
+    #[inline]
+    pub fn substandard(&self) -> bool {
+        self.substandard
+    }
+
     /// Returns stemming if the word was found, `None` otherwise.
     ///
     /// Stems are a list of potential root words, including the word itself. This list may
     /// contain duplicates (collect to a [`HashSet`](std::collections::HashSet) or use
-    /// [`Vec::dedup`](std::vec::Vec::dedup) if this is needed).
+    /// [`Vec::dedup`](std::vec::Vec::dedup) if this is needed). Output conversions (`OCONV`)
+    /// are applied if the dictionary specifies any.
     ///
     /// Note that for this to be most useful, you need a dictionary that contains stemming
     /// information, but these are less common. Some of the [SCOWL] dictionaries provide this
@@ -570,7 +1528,7 @@ impl<'dict, 'word> WordEntry<'dict, 'word> {
     /// assert_eq!(stems, ["drink"]);
     /// ```
     #[inline]
-    pub fn stems(&self) -> Option<impl Iterator<Item = &str>> {
+    pub fn stems(&self) -> Option<impl Iterator<Item = Cow<'dict, str>>> {
         // FIXME: we can probably be smarter about what we send here to avoid duplicates without
         // manual tracking. Maybe not printing a dictionary stem if one for affixes exists, or
         // something like that. Or we be very sneaky and keep a `RefCell<Vec<u32>>` in our
@@ -578,28 +1536,26 @@ impl<'dict, 'word> WordEntry<'dict, 'word> {
         // call. Needs benchmarking.
         let mut visited: Vec<u32> = Vec::new();
 
-        let WordCtx::Correct {
-            matched: _,
-            meta_list,
-        } = self.context
-        else {
+        let WordCtx::Correct { meta_list, .. } = &self.context else {
             return None;
         };
 
-        let ret = meta_list.iter().flat_map(|meta| {
-            // Combine the main stem with every stem provided by morphs
-            let stem = std::iter::once(meta.stem());
-            let morph_stems = meta.source().morphs().filter_map(|morph| match morph {
-                MorphInfo::Stem(v) => Some(v.as_ref()),
-                _ => None,
-            });
+        let cfg = &self.dict.parsed_config;
+        // Meta created by stripping affixes is not owned by the dictionary, so its stems
+        // need to be copied
+        let ret: Vec<Cow<'dict, str>> = match meta_list {
+            Cow::Borrowed(list) => meta_stems(list)
+                .map(|stem| cfg.convert_output(stem))
+                .collect(),
+            Cow::Owned(list) => meta_stems(list)
+                .map(|stem| Cow::Owned(cfg.convert_output(stem).into_owned()))
+                .collect(),
+        };
 
-            stem.chain(morph_stems)
-        });
         // remove self because we will include that at the beginning
         // let ret = ret.filter(move |value| value != &matched);
         // deduplicate
-        let ret = ret.filter(move |value| {
+        let ret = ret.into_iter().filter(move |value| {
             let hash = xxh32(value.as_bytes(), 0);
             if visited.contains(&hash) {
                 false
@@ -646,24 +1602,103 @@ impl<'dict, 'word> WordEntry<'dict, 'word> {
     /// ```
     #[inline]
     pub fn analyze(&self) -> Option<impl Iterator<Item = &MorphInfo>> {
-        let WordCtx::Correct { meta_list, .. } = self.context else {
+        let WordCtx::Correct { meta_list, .. } = &self.context else {
             return None;
         };
         let ret = meta_list.iter().flat_map(|meta| meta.source().morphs());
         Some(ret)
     }
 
+    /// Morphological analysis with one list for each way the word can be created, in the
+    /// same form as Hunspell's `analyze`. Returns `None` if the word is not correct.
+    ///
+    /// Each analysis starts with the stem (`st:`, with output conversions applied),
+    /// followed by fields from the dictionary entry and then fields from each affix
+    /// in the order they were applied. Duplicate analyses are removed.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let affix_str = "
+    /// SFX S Y 1
+    /// SFX S 0 s . is:plural
+    ///
+    /// SFX V Y 1
+    /// SFX V 0 s . is:sg_3
+    /// ";
+    /// let dict_str = "
+    /// drink/S po:noun
+    /// drink/V po:verb
+    /// ";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str(dict_str)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let entry = dict.entry("drinks");
+    /// let mut lines: Vec<String> = entry
+    ///     .analyses()
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|analysis| {
+    ///         let fields: Vec<_> = analysis.iter().map(ToString::to_string).collect();
+    ///         fields.join(" ")
+    ///     })
+    ///     .collect();
+    /// lines.sort_unstable();
+    ///
+    /// assert_eq!(
+    ///     lines,
+    ///     ["st:drink po:noun is:plural", "st:drink po:verb is:sg_3"]
+    /// );
+    /// ```
+    #[inline]
+    pub fn analyses(&self) -> Option<Vec<Vec<Cow<'_, MorphInfo>>>> {
+        let WordCtx::Correct { meta_list, .. } = &self.context else {
+            return None;
+        };
+
+        let mut ret: Vec<Vec<Cow<'_, MorphInfo>>> = Vec::new();
+        for derivation in split_derivations(meta_list) {
+            let (affixes, entries): (Vec<&Meta>, Vec<&Meta>) = derivation
+                .iter()
+                .partition(|meta| matches!(meta.source(), Source::Affix { .. }));
+
+            // The entry's stem takes `st:` into account
+            let stem = entries.first().unwrap_or(&&derivation[0]).stem();
+            let stem = self.dict.parsed_config.convert_output(stem);
+            let mut analysis = vec![Cow::Owned(MorphInfo::Stem(stem.as_ref().into()))];
+
+            let entry_morphs = entries.iter().flat_map(|meta| meta.source().morphs());
+            analysis.extend(
+                entry_morphs
+                    .filter(|morph| !matches!(morph, MorphInfo::Stem(_)))
+                    .map(Cow::Borrowed),
+            );
+            let affix_morphs = affixes.iter().flat_map(|meta| meta.source().morphs());
+            analysis.extend(affix_morphs.map(Cow::Borrowed));
+
+            if !ret.contains(&analysis) {
+                ret.push(analysis);
+            }
+        }
+
+        Some(ret)
+    }
+
     /// Suggest replacements for a word. Feature gated behind `unstable-suggestions`.
     ///
     /// If the word is correct, this will return `None`. Otherwise, it will return an
-    /// iterator over suggested words.
+    /// iterator over suggested words, with output conversions (`OCONV`) applied.
     ///
     /// This function is unstable because it has performance issues. We are
     /// going to try to speed up the algorithm significantly.
     // PERF: bench with par_iter
     #[inline]
     #[cfg(feature = "unstable-suggestions")]
-    pub fn suggest(&self) -> Option<Vec<&str>> {
+    pub fn suggest(&self) -> Option<Vec<Cow<'dict, str>>> {
         if self.correct() {
             return None;
         };
@@ -673,10 +1708,21 @@ impl<'dict, 'word> WordEntry<'dict, 'word> {
             .wordlist
             .0
             .keys()
+            .filter(|key| {
+                !self.dict.warn.contains(key.as_ref())
+                    && !self.dict.substandard.contains(key.as_ref())
+            })
             .filter_map(|key| try_levenshtein(key, self.word, 1).map(|lim| (lim, key.as_ref())))
             .collect();
         suggestions.sort_unstable_by_key(|(k, _v)| *k);
-        Some(suggestions.iter().take(10).map(|(_k, v)| *v).collect())
+        let cfg = &self.dict.parsed_config;
+        Some(
+            suggestions
+                .iter()
+                .take(10)
+                .map(|(_k, v)| cfg.convert_output(v))
+                .collect(),
+        )
     }
 }
 
@@ -708,9 +1754,58 @@ impl WordList {
 #[derive(Clone, Debug, PartialEq)]
 pub struct DictBuilder<'a> {
     cfg: Option<ParsedCfg>,
-    cfg_src: Option<&'a str>,
-    dict_src: Option<&'a str>,
+    cfg_src: Option<Input<'a>>,
+    dict_src: Option<Input<'a>>,
     personal_src: Option<&'a str>,
+    word_list_src: Option<&'a str>,
+    strategy: Strategy,
+    cache_path: Option<&'a Path>,
+    parallel: bool,
+}
+
+/// How a [`Dictionary`] stores words created from affix rules, chosen with
+/// [`DictBuilder::expansion`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Create every affixed form of every stem when building. This uses more memory
+    /// and takes longer to load, but checking a word is a single lookup.
+    #[default]
+    Eager,
+    /// Only store stems, and find affixed words by stripping possible affixes from the
+    /// input when checking. This loads faster and uses far less memory, especially for
+    /// languages with many affixes, but each check does more work.
+    ///
+    /// Suggestions only include words that are stored, so affixed forms are not
+    /// suggested with this strategy.
+    Lazy,
+}
+
+/// Source file contents given to the builder
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Input<'a> {
+    /// Already UTF-8
+    Str(&'a str),
+    /// Raw bytes in the encoding specified by `SET`
+    Bytes(&'a [u8]),
+}
+
+impl<'a> Input<'a> {
+    /// Get the UTF-8 contents, decoding with the given encoding if needed
+    fn decode(self, encoding: &Encoding) -> Result<Cow<'a, str>, BuildError> {
+        match self {
+            Input::Str(s) => Ok(Cow::Borrowed(s)),
+            Input::Bytes(b) => encoding.decode(b),
+        }
+    }
+
+    /// The original contents
+    fn as_bytes(self) -> &'a [u8] {
+        match self {
+            Input::Str(s) => s.as_bytes(),
+            Input::Bytes(b) => b,
+        }
+    }
 }
 
 impl<'a> DictBuilder<'a> {
@@ -722,13 +1817,29 @@ impl<'a> DictBuilder<'a> {
             cfg_src: None,
             dict_src: None,
             personal_src: None,
+            word_list_src: None,
+            strategy: Strategy::Eager,
+            cache_path: None,
+            parallel: cfg!(feature = "parallel"),
         }
     }
 
     /// Load the affix file from the given string.
     #[inline]
     pub fn config_str(mut self, config: &'a str) -> Self {
-        self.cfg_src = Some(config);
+        self.cfg_src = Some(Input::Str(config));
+        self
+    }
+
+    /// Load the affix file from raw bytes, such as the unmodified contents of a
+    /// `.aff` file.
+    ///
+    /// The encoding is taken from the `SET` line (UTF-8 if unspecified) and the
+    /// contents are converted to UTF-8 before parsing. [`build`](Self::build) returns an
+    /// error if the encoding is not supported or the bytes are not valid for it.
+    #[inline]
+    pub fn config_bytes(mut self, config: &'a [u8]) -> Self {
+        self.cfg_src = Some(Input::Bytes(config));
         self
     }
 
@@ -745,7 +1856,88 @@ impl<'a> DictBuilder<'a> {
     /// Load the dictionary file from a string
     #[inline]
     pub fn dict_str(mut self, dict: &'a str) -> Self {
-        self.dict_src = Some(dict);
+        self.dict_src = Some(Input::Str(dict));
+        self
+    }
+
+    /// Load the dictionary file from raw bytes, such as the unmodified contents of a
+    /// `.dic` file. These are decoded using the encoding from the affix file's `SET`.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// // "café" and "naïve" encoded as ISO8859-1
+    /// let affix = b"SET ISO8859-1\n";
+    /// let dict = b"2\ncaf\xe9\nna\xefve\n";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_bytes(affix)
+    ///     .dict_bytes(dict)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(dict.check("café naïve"));
+    /// ```
+    #[inline]
+    pub fn dict_bytes(mut self, dict: &'a [u8]) -> Self {
+        self.dict_src = Some(Input::Bytes(dict));
+        self
+    }
+
+    /// Set how words created from affix rules are stored. The default is
+    /// [`Strategy::Eager`].
+    ///
+    /// ```
+    /// use zspell::{DictBuilder, Strategy};
+    ///
+    /// let affix_str = "
+    /// SFX A Y 1
+    /// SFX A 0 ing .
+    /// ";
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .config_str(affix_str)
+    ///     .dict_str("drink/A")
+    ///     .expansion(Strategy::Lazy)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(dict.check("drink drinking"));
+    /// assert!(!dict.check("drinked"));
+    /// ```
+    #[inline]
+    pub fn expansion(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Use a compiled cache file to speed up loading.
+    ///
+    /// If the file exists and was created from the same sources by the same version of
+    /// this crate, the dictionary is loaded from it without expanding affixes. Otherwise,
+    /// the dictionary is built as usual and the cache file is (re)written. A checksum of
+    /// the affix, dictionary and personal dictionary sources is stored in the cache, so
+    /// it is rebuilt automatically when any of them change.
+    ///
+    /// The cache is written to a temporary file and then renamed into place, so a
+    /// partially written cache is never loaded. If it cannot be written (e.g. the
+    /// directory is read-only), the dictionary is still returned.
+    ///
+    /// This has no effect if the configuration was not given as a string or bytes.
+    #[inline]
+    pub fn cache_path(mut self, path: &'a Path) -> Self {
+        self.cache_path = Some(path);
+        self
+    }
+
+    /// Set whether to parse and expand dictionary entries on multiple threads. This is
+    /// enabled by default with the `parallel` feature, which is required to use it.
+    ///
+    /// The resulting dictionary is the same either way.
+    #[inline]
+    #[cfg(feature = "parallel")]
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
         self
     }
 
@@ -756,40 +1948,152 @@ impl<'a> DictBuilder<'a> {
         self
     }
 
+    /// Load a plain list of words from a string, one per line. Unlike a dictionary
+    /// file, there is no word count and no flags, so each line is accepted exactly as
+    /// written. This works for files such as `/usr/share/dict/words` or lists of
+    /// identifiers.
+    ///
+    /// A word list may be used without an affix file, in which case an empty
+    /// configuration is used.
+    ///
+    /// ```
+    /// use zspell::DictBuilder;
+    ///
+    /// let dict = DictBuilder::new()
+    ///     .word_list_str("apple\nbanana\nHashMap\n")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(dict.check("apple HashMap"));
+    /// assert!(!dict.check("apples"));
+    /// ```
+    #[inline]
+    pub fn word_list_str(mut self, words: &'a str) -> Self {
+        self.word_list_src = Some(words);
+        self
+    }
+
     /// Consume this builder and return a `Dictionary`
     ///
     /// # Errors
     ///
     /// Returns an error if anything went wrong with parsing, or if the builder
     /// was in some way misconfigured.
-    // PERF: parallize parsing of affix & dict files
     #[inline]
     pub fn build(self) -> Result<Dictionary, Error> {
         if self.cfg.is_some() && self.cfg_src.is_some() {
             return Err(Error::Build(BuildError::BuilderCfgSpecTwice));
         }
 
+        // Caching needs the configuration source
+        let checksum = self
+            .cache_path
+            .zip(self.cfg_input())
+            .map(|_| self.checksum());
+        if let Some((path, sum)) = self.cache_path.zip(checksum) {
+            if let Some(dict) = fs::read(path).ok().and_then(|b| cache::read(&b, sum)) {
+                return Ok(dict);
+            }
+        }
+
+        let cache_path = self.cache_path;
+        let (dict, cfg_text) = self.build_inner()?;
+
+        // The cache only speeds up loading, so failing to write it is not an error
+        if let (Some(path), Some(sum), Some(text)) = (cache_path, checksum, cfg_text) {
+            let _ = cache::save(path, &cache::write(&dict, &text, sum));
+        }
+
+        Ok(dict)
+    }
+
+    /// Consume this builder and return the dictionary in a compact binary format, to be
+    /// loaded with [`CompactDictionary::new`]. The result can be saved to a file and
+    /// memory mapped.
+    ///
+    /// Affixes are always expanded, [`expansion`](Self::expansion) is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if anything went wrong with parsing, or if the configuration
+    /// was not given as a string or bytes.
+    #[inline]
+    pub fn build_compact(self) -> Result<Vec<u8>, Error> {
+        if self.cfg.is_some() && self.cfg_src.is_some() {
+            return Err(Error::Build(BuildError::BuilderCfgSpecTwice));
+        }
+
+        let (dict, cfg_text) = self.expansion(Strategy::Eager).build_inner()?;
+        let text = cfg_text.ok_or(Error::Build(BuildError::BuilderCfgUnspecified))?;
+        Ok(compact::write(&dict, &text))
+    }
+
+    /// Build the dictionary, also returning the decoded configuration source if it was
+    /// provided
+    fn build_inner(self) -> Result<(Dictionary, Option<Cow<'a, str>>), Error> {
+        let mut cfg_text = None;
         let cfg = if let Some(c) = self.cfg {
             c
-        } else if let Some(cs) = self.cfg_src {
-            ParsedCfg::load_from_str(cs)?
+        } else if let Some(cs) = self.cfg_input() {
+            let encoding = match cs {
+                Input::Str(_) => Encoding::Utf8,
+                Input::Bytes(b) => {
+                    Encoding::sniff(b).map_err(|e| ParseError::new_nospan(e, "SET"))?
+                }
+            };
+            let text = cs.decode(&encoding)?;
+            let cfg = ParsedCfg::load_from_str(&text)?;
+            cfg_text = Some(text);
+            cfg
         } else {
             return Err(Error::Build(BuildError::BuilderCfgUnspecified));
         };
 
         let mut dict = Dictionary::new(cfg)?;
+        if self.strategy == Strategy::Lazy {
+            dict.lazy = Some(Box::new(LazyIndex::new(&dict.affix_flags)));
+        }
 
         if let Some(wl) = self.dict_src {
-            dict.parse_update_wordlist(wl)?;
+            let wl = wl.decode(dict.parsed_config.encoding())?;
+            dict.parse_update_wordlist(&wl, self.parallel)?;
+        }
+
+        if let Some(wl) = self.word_list_src {
+            dict.add_word_list(wl);
         }
 
         if let Some(wl) = self.personal_src {
-            dict.parse_update_personal(wl, &[])?;
+            dict.parse_update_personal(wl)?;
         }
 
         dict.shrink_storage();
 
-        Ok(dict)
+        Ok((dict, cfg_text))
+    }
+
+    /// The configuration source. A word list on its own uses an empty configuration.
+    fn cfg_input(&self) -> Option<Input<'a>> {
+        match (&self.cfg, self.cfg_src, self.word_list_src) {
+            (None, None, Some(_)) => Some(Input::Str("")),
+            (_, cs, _) => cs,
+        }
+    }
+
+    /// Checksum of everything that affects the built dictionary
+    fn checksum(&self) -> u64 {
+        let strategy: &[u8] = match self.strategy {
+            Strategy::Eager => b"eager",
+            Strategy::Lazy => b"lazy",
+        };
+
+        cache::checksum(&[
+            self.cfg_input().map_or(&[], Input::as_bytes),
+            self.dict_src.map_or(&[], Input::as_bytes),
+            self.personal_src.map_or(&[], str::as_bytes),
+            self.word_list_src.map_or(&[], str::as_bytes),
+            strategy,
+        ])
     }
 }
 
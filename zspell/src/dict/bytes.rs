@@ -0,0 +1,58 @@
+//! Reading the little endian binary formats used by the dictionary cache and
+//! [`CompactDictionary`](super::CompactDictionary)
+
+/// Reads values from the front of a byte slice. Every method returns `None` if there
+/// are not enough bytes left, so truncated input is never a panic.
+pub(super) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// The bytes that have not been read yet
+    pub(super) fn remaining(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    pub(super) fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Some(taken)
+    }
+
+    pub(super) fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    pub(super) fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub(super) fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    /// A length or index stored as a `u32`
+    pub(super) fn len(&mut self) -> Option<usize> {
+        self.u32()?.try_into().ok()
+    }
+
+    /// The number of items that follow, used to reserve space for them. Every item takes
+    /// at least one byte, so a count larger than the remaining input is invalid and never
+    /// causes a large allocation.
+    pub(super) fn count(&mut self) -> Option<usize> {
+        self.len().filter(|count| *count <= self.buf.len())
+    }
+
+    /// A `u32` byte length followed by UTF-8
+    pub(super) fn str(&mut self) -> Option<&'a str> {
+        let len = self.len()?;
+        std::str::from_utf8(self.take(len)?).ok()
+    }
+}
@@ -0,0 +1,431 @@
+//! Compiled dictionary cache: a binary file containing everything needed to load a
+//! [`Dictionary`] without expanding affixes again.
+//!
+//! All integers are little endian. Strings are a `u32` byte length followed by UTF-8.
+//! The layout is:
+//!
+//! - Header: magic `ZSPC`, format version (`u32`), crate version (string), and a
+//!   checksum of the source files (`u64`)
+//! - The affix file source (string). Rules are recreated from this on load, and metas
+//!   refer to them by flag.
+//! - A table of stems (`u32` count, strings) that metas refer to by index
+//! - The wordlist, nosuggest and forbidden wordlists: a `u32` count, then each word
+//!   (string) with its metas (`u32` count, metas)
+//! - `WARN` and `SUBSTANDARD` word sets (`u32` count, strings)
+//! - Flags of each stem (`u32` count, then a stem index, `u32` flag count and flags)
+//! - A `u8` that is 1 if stems for [`Strategy::Lazy`](super::Strategy::Lazy) follow
+//!
+//! A meta is a stem index (`u32`) and a tag (`u8`) for its source, followed by:
+//! `0` affix: rule flag (`u32`) and pattern index (`u32`); `1` dictionary: morphs;
+//! `2` personal: a `u8` that is 1 if a friend string follows, then morphs; `3` raw.
+//! Morphs are a `u32` count followed by each morph as a string.
+
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fs, io, process};
+
+use hashbrown::{HashMap, HashSet};
+use xxhash_rust::xxh64::Xxh64;
+
+use super::bytes::Reader;
+use super::meta::{Meta, PersonalMeta, Source};
+use super::rule::AfxRule;
+use super::rules_reverse::{LazyIndex, LazyStem, StemAttrs};
+use super::{Dictionary, Flag, FlagValue};
+use crate::morph::MorphInfo;
+use crate::ParsedCfg;
+
+const MAGIC: &[u8; 4] = b"ZSPC";
+/// Increment whenever the layout changes
+const FORMAT_VERSION: u32 = 2;
+
+/// Checksum of source files, used to detect stale caches
+pub(super) fn checksum(sources: &[&[u8]]) -> u64 {
+    let mut hasher = Xxh64::new(0);
+    for source in sources {
+        hasher.update(&(source.len() as u64).to_le_bytes());
+        hasher.update(source);
+    }
+    hasher.digest()
+}
+
+/// Write a cache file atomically: the data goes to a temporary file in the same
+/// directory, which is then renamed over `path`. Readers never see a partial file.
+pub(super) fn save(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(format!(".{}.tmp", process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let res = fs::write(&tmp_path, bytes).and_then(|()| fs::rename(&tmp_path, path));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    res
+}
+
+/// Serialize a dictionary that was built from `cfg_src`
+pub(super) fn write(dict: &Dictionary, cfg_src: &str, checksum: u64) -> Vec<u8> {
+    let mut w = Writer::new(dict);
+    w.buf.extend_from_slice(MAGIC);
+    w.u32(FORMAT_VERSION);
+    w.str(env!("CARGO_PKG_VERSION"));
+    w.buf.extend_from_slice(&checksum.to_le_bytes());
+    w.str(cfg_src);
+
+    w.len(w.stems.len());
+    for stem in w.stems.clone() {
+        w.str(&stem);
+    }
+
+    for wordlist in [
+        &dict.wordlist,
+        &dict.wordlist_nosuggest,
+        &dict.wordlist_forbidden,
+    ] {
+        w.len(wordlist.0.len());
+        for (word, metas) in &wordlist.0 {
+            w.str(word);
+            w.len(metas.len());
+            for meta in metas {
+                w.meta(meta);
+            }
+        }
+    }
+
+    for set in [&dict.warn, &dict.substandard] {
+        w.len(set.len());
+        for word in set {
+            w.str(word);
+        }
+    }
+
+    w.len(dict.stem_flags.len());
+    for (stem, flags) in &dict.stem_flags {
+        w.u32(w.stem_idx(stem));
+        w.len(flags.len());
+        for flag in flags.iter() {
+            w.u32(flag.0);
+        }
+    }
+
+    if let Some(lazy) = &dict.lazy {
+        w.buf.push(1);
+        w.len(lazy.stems().count());
+        for (stem, entries) in lazy.stems() {
+            w.u32(w.stem_idx(stem));
+            w.len(entries.len());
+            for entry in entries {
+                w.lazy_stem(entry);
+            }
+        }
+    } else {
+        w.buf.push(0);
+    }
+
+    w.buf
+}
+
+/// Load a dictionary from cache bytes. Returns `None` if the cache is from a different
+/// version, does not match `checksum`, or cannot be read.
+pub(super) fn read(bytes: &[u8], checksum: u64) -> Option<Dictionary> {
+    let mut r = Reader::new(bytes);
+    if r.take(MAGIC.len())? != MAGIC
+        || r.u32()? != FORMAT_VERSION
+        || r.str()? != env!("CARGO_PKG_VERSION")
+        || r.u64()? != checksum
+    {
+        return None;
+    }
+
+    let cfg = ParsedCfg::load_from_str(r.str()?).ok()?;
+    let mut dict = Dictionary::new(cfg).ok()?;
+    let rules = rules_by_flag(&dict);
+
+    let stem_count = r.count()?;
+    let mut stems = Vec::with_capacity(stem_count);
+    for _ in 0..stem_count {
+        let stem: Arc<str> = r.str()?.into();
+        dict.stems.insert(Arc::clone(&stem));
+        stems.push(stem);
+    }
+
+    let mut ctx = LoadCtx {
+        stems,
+        rules,
+        morphs: HashSet::new(),
+    };
+
+    for wordlist in [
+        &mut dict.wordlist,
+        &mut dict.wordlist_nosuggest,
+        &mut dict.wordlist_forbidden,
+    ] {
+        let count = r.count()?;
+        wordlist.0.reserve(count);
+        for _ in 0..count {
+            let word: Box<str> = r.str()?.into();
+            let meta_count = r.count()?;
+            let mut metas = Vec::with_capacity(meta_count);
+            for _ in 0..meta_count {
+                metas.push(r.meta(&mut ctx)?);
+            }
+            wordlist.0.insert(word, metas);
+        }
+    }
+
+    for set in [&mut dict.warn, &mut dict.substandard] {
+        for _ in 0..r.len()? {
+            set.insert(r.str()?.into());
+        }
+    }
+
+    for _ in 0..r.len()? {
+        let stem = Arc::clone(ctx.stems.get(r.len()?)?);
+        let count = r.count()?;
+        let mut flags = Vec::with_capacity(count);
+        for _ in 0..count {
+            flags.push(Flag(r.u32()?));
+        }
+        dict.stem_flags.insert(stem, flags.into());
+    }
+
+    if r.u8()? == 1 {
+        let mut lazy = LazyIndex::new(&dict.affix_flags);
+        for _ in 0..r.len()? {
+            let stem = Arc::clone(ctx.stems.get(r.len()?)?);
+            for _ in 0..r.len()? {
+                lazy.insert(&stem, r.lazy_stem(&mut ctx)?);
+            }
+        }
+        dict.lazy = Some(Box::new(lazy));
+    }
+
+    if !r.remaining().is_empty() {
+        return None;
+    }
+
+    // Entries that name another stem are found from their dictionary metadata
+    let stored = [
+        &dict.wordlist,
+        &dict.wordlist_nosuggest,
+        &dict.wordlist_forbidden,
+    ]
+    .into_iter()
+    .flat_map(|list| list.0.values().flatten());
+    let lazy = dict
+        .lazy
+        .iter()
+        .flat_map(|index| index.stems())
+        .flat_map(|(_, entries)| entries.iter().filter_map(|e| e.dict_meta.as_ref()));
+    let dict_metas: Vec<Meta> = stored
+        .chain(lazy)
+        .filter(|meta| matches!(meta.source(), Source::Dict(_)))
+        .cloned()
+        .collect();
+    for meta in dict_metas {
+        if let Source::Dict(morph) = meta.source() {
+            dict.index_alt_stem(meta.stem_rc(), morph);
+        }
+    }
+
+    dict.morphs = ctx.morphs;
+    dict.shrink_storage();
+    Some(dict)
+}
+
+/// Map each affix rule to the flag that selects it
+fn rules_by_flag(dict: &Dictionary) -> HashMap<Flag, Arc<AfxRule>> {
+    dict.affix_flags
+        .iter()
+        .filter_map(|(flag, value)| match value {
+            FlagValue::Rule(rule) => Some((*flag, Arc::clone(rule))),
+            _ => None,
+        })
+        .collect()
+}
+
+struct Writer {
+    buf: Vec<u8>,
+    /// Stems in order of their index
+    stems: Vec<Arc<str>>,
+    stem_indices: HashMap<Arc<str>, u32>,
+    /// Flag for each rule, keyed by the rule's address
+    rule_flags: HashMap<*const AfxRule, Flag>,
+}
+
+impl Writer {
+    fn new(dict: &Dictionary) -> Self {
+        let mut stems: Vec<Arc<str>> = dict.stems.iter().cloned().collect();
+        stems.sort_unstable();
+        let stem_indices = stems
+            .iter()
+            .enumerate()
+            .map(|(idx, stem)| (Arc::clone(stem), u32::try_from(idx).unwrap()))
+            .collect();
+        let rule_flags = rules_by_flag(dict)
+            .into_iter()
+            .map(|(flag, rule)| (Arc::as_ptr(&rule), flag))
+            .collect();
+
+        Self {
+            buf: Vec::new(),
+            stems,
+            stem_indices,
+            rule_flags,
+        }
+    }
+
+    fn u32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u32(u32::try_from(len).expect("too many items to cache"));
+    }
+
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn stem_idx(&self, stem: &str) -> u32 {
+        self.stem_indices[stem]
+    }
+
+    fn morphs<'a>(&mut self, morphs: impl ExactSizeIterator<Item = &'a Arc<MorphInfo>>) {
+        self.len(morphs.len());
+        for morph in morphs {
+            self.str(&morph.to_string());
+        }
+    }
+
+    fn meta(&mut self, meta: &Meta) {
+        self.u32(self.stem_idx(meta.stem_rc()));
+        match meta.source() {
+            Source::Affix { rule, pat_idx } => {
+                self.buf.push(0);
+                self.u32(self.rule_flags[&Arc::as_ptr(rule)].0);
+                self.len(*pat_idx);
+            }
+            Source::Dict(morphs) => {
+                self.buf.push(1);
+                self.morphs(morphs.iter());
+            }
+            Source::Personal(personal) => {
+                self.buf.push(2);
+                if let Some(friend) = personal.friend() {
+                    self.buf.push(1);
+                    self.str(friend);
+                } else {
+                    self.buf.push(0);
+                }
+                self.morphs(personal.morph().iter());
+            }
+            Source::Raw => self.buf.push(3),
+        }
+    }
+
+    fn lazy_stem(&mut self, entry: &LazyStem) {
+        self.len(entry.rules.len());
+        for rule in &entry.rules {
+            self.u32(self.rule_flags[&Arc::as_ptr(rule)].0);
+        }
+
+        if let Some(meta) = &entry.dict_meta {
+            self.buf.push(1);
+            self.meta(meta);
+        } else {
+            self.buf.push(0);
+        }
+
+        let StemAttrs {
+            forbid,
+            nosuggest,
+            warn,
+            substandard,
+        } = entry.attrs;
+        let attrs = [forbid, nosuggest, warn, substandard]
+            .iter()
+            .enumerate()
+            .fold(0u8, |acc, (bit, set)| acc | (u8::from(*set) << bit));
+        self.buf.push(attrs);
+    }
+}
+
+/// Shared values that loaded metas point to
+struct LoadCtx {
+    stems: Vec<Arc<str>>,
+    rules: HashMap<Flag, Arc<AfxRule>>,
+    morphs: HashSet<Arc<MorphInfo>>,
+}
+
+/// Cache-specific values, built on the primitives in [`Reader`]
+impl Reader<'_> {
+    fn morphs(&mut self, ctx: &mut LoadCtx) -> Option<Vec<Arc<MorphInfo>>> {
+        let count = self.count()?;
+        let mut ret = Vec::with_capacity(count);
+        for _ in 0..count {
+            let morph = MorphInfo::from(self.str()?);
+            ret.push(Arc::clone(
+                ctx.morphs
+                    .get_or_insert_with(&morph, |m| Arc::new(m.clone())),
+            ));
+        }
+        Some(ret)
+    }
+
+    fn meta(&mut self, ctx: &mut LoadCtx) -> Option<Meta> {
+        let stem = Arc::clone(ctx.stems.get(self.len()?)?);
+        let source = match self.u8()? {
+            0 => {
+                let rule = ctx.rules.get(&Flag(self.u32()?))?;
+                let pat_idx = self.len()?;
+                if pat_idx >= rule.patterns().len() {
+                    return None;
+                }
+                Source::new_affix(rule, pat_idx)
+            }
+            1 => Source::Dict(self.morphs(ctx)?.into()),
+            2 => {
+                let friend = match self.u8()? {
+                    1 => Some(self.str()?.into()),
+                    _ => None,
+                };
+                Source::Personal(Arc::new(PersonalMeta::new(friend, self.morphs(ctx)?)))
+            }
+            3 => Source::Raw,
+            _ => return None,
+        };
+
+        Some(Meta::new(stem, source))
+    }
+
+    fn lazy_stem(&mut self, ctx: &mut LoadCtx) -> Option<LazyStem> {
+        let rule_count = self.count()?;
+        let mut rules = Vec::with_capacity(rule_count);
+        for _ in 0..rule_count {
+            rules.push(Arc::clone(ctx.rules.get(&Flag(self.u32()?))?));
+        }
+
+        let dict_meta = match self.u8()? {
+            1 => Some(self.meta(ctx)?),
+            _ => None,
+        };
+
+        let attrs = self.u8()?;
+        let bit = |idx: u8| attrs & (1 << idx) != 0;
+
+        Some(LazyStem {
+            rules,
+            dict_meta,
+            attrs: StemAttrs {
+                forbid: bit(0),
+                nosuggest: bit(1),
+                warn: bit(2),
+                substandard: bit(3),
+            },
+        })
+    }
+}
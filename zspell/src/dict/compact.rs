@@ -0,0 +1,418 @@
+//! A compact, read-only dictionary format that is used directly from its bytes.
+//!
+//! Words are stored in a finite state transducer (FST) that maps each word to the offset
+//! of a record. Nothing is copied when loading, so the bytes can come from a memory map
+//! and be shared among processes.
+//!
+//! All integers are little endian. The layout is:
+//!
+//! - Header: magic `ZSPK`, format version (`u32`), and crate version (`u32` byte length
+//!   followed by UTF-8)
+//! - The affix file source (same string encoding as the version). This is parsed on
+//!   load for settings such as conversions and word characters.
+//! - The byte lengths (`u64`) of the four following sections
+//! - The FST, mapping each word to the offset of its record
+//! - Records: an attribute byte, a `u32` stem count, then each stem index (`u32`).
+//!   Identical records are shared.
+//! - Stem ends: the end offset (`u32`) of each stem within the stem data
+//! - Stem data: all stems as concatenated UTF-8
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use fst::{Map, MapBuilder};
+use hashbrown::HashMap;
+
+use super::bytes::Reader;
+use super::{meta_stems, strip_period, word_splitter, Dictionary};
+use crate::error::{BuildError, Error};
+use crate::ParsedCfg;
+
+const MAGIC: &[u8; 4] = b"ZSPK";
+/// Increment whenever the layout changes
+const FORMAT_VERSION: u32 = 1;
+
+/* record attribute bits */
+/// In the main or nosuggest wordlist
+const ACCEPT: u8 = 1 << 0;
+/// In the forbidden wordlist
+const FORBID: u8 = 1 << 1;
+const NOSUGGEST: u8 = 1 << 2;
+const WARN: u8 = 1 << 3;
+const SUBSTANDARD: u8 = 1 << 4;
+
+/// A read-only dictionary stored in a compact binary format, created with
+/// [`DictBuilder::build_compact`](super::DictBuilder::build_compact).
+///
+/// A [`Dictionary`] stores every word in a hash map with its metadata, which can take
+/// hundreds of megabytes for large dictionaries. This type instead reads words from a
+/// minimal automaton and packed stem indices, directly from the provided data. `D` can be
+/// anything that dereferences to bytes, such as a `Vec<u8>` or a memory-mapped file, and
+/// the data is never copied.
+///
+/// Spellchecking and stemming are supported. Morphological analysis and suggestions need
+/// a full [`Dictionary`].
+///
+/// ```
+/// # #![cfg(not(miri))]
+/// use std::fs;
+///
+/// use zspell::{CompactDictionary, DictBuilder};
+///
+/// let aff_content = fs::read_to_string("tests/files/w1_eng_short.aff").unwrap();
+/// let dic_content = fs::read_to_string("tests/files/w1_eng_short.dic").unwrap();
+///
+/// let bytes: Vec<u8> = DictBuilder::new()
+///     .config_str(&aff_content)
+///     .dict_str(&dic_content)
+///     .build_compact()
+///     .unwrap();
+///
+/// let dict = CompactDictionary::new(bytes).unwrap();
+///
+/// assert!(dict.check("reptiles pillow bananas"));
+/// assert!(!dict.check("pine missssspelled"));
+/// ```
+///
+/// Since this crate does not use `unsafe`, mapping a file into memory is left to the
+/// caller. With the [`memmap2`](https://docs.rs/memmap2) crate, several processes that
+/// open the same file share a single copy of the dictionary:
+///
+/// ```
+/// # #![cfg(not(miri))]
+/// # use std::fs;
+/// # use zspell::{CompactDictionary, DictBuilder};
+/// # let aff_content = fs::read_to_string("tests/files/w1_eng_short.aff").unwrap();
+/// # let dic_content = fs::read_to_string("tests/files/w1_eng_short.dic").unwrap();
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("en.zspk");
+/// let bytes = DictBuilder::new()
+///     .config_str(&aff_content)
+///     .dict_str(&dic_content)
+///     .build_compact()
+///     .unwrap();
+/// fs::write(&path, bytes).unwrap();
+///
+/// let file = fs::File::open(&path).unwrap();
+/// // SAFETY: the file must not be modified while it is mapped
+/// let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
+/// let dict = CompactDictionary::new(mmap).unwrap();
+///
+/// assert!(dict.check_word("bananas"));
+/// ```
+#[must_use]
+pub struct CompactDictionary<D> {
+    /// Map of words to record offsets
+    words: Map<Section<D>>,
+    records: Section<D>,
+    stem_ends: Section<D>,
+    stem_data: Section<D>,
+    parsed_config: Box<ParsedCfg>,
+}
+
+impl<D: AsRef<[u8]>> CompactDictionary<D> {
+    /// Load a dictionary from data created by
+    /// [`DictBuilder::build_compact`](super::DictBuilder::build_compact).
+    ///
+    /// Only the header and affix configuration are parsed, words are read from `data`
+    /// as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::InvalidCompact`] if `data` is not in the compact format or
+    /// was created by a different version of this crate.
+    #[inline]
+    pub fn new(data: D) -> Result<Self, Error> {
+        let invalid = || Error::Build(BuildError::InvalidCompact);
+        let data = Arc::new(data);
+        let bytes = (*data).as_ref();
+
+        let mut r = Reader::new(bytes);
+        if r.take(MAGIC.len()).ok_or_else(invalid)? != MAGIC
+            || r.u32().ok_or_else(invalid)? != FORMAT_VERSION
+            || r.str().ok_or_else(invalid)? != env!("CARGO_PKG_VERSION")
+        {
+            return Err(invalid());
+        }
+
+        let cfg = ParsedCfg::load_from_str(r.str().ok_or_else(invalid)?)?;
+
+        let mut lens = [0usize; 4];
+        for len in &mut lens {
+            *len = r
+                .u64()
+                .and_then(|v| v.try_into().ok())
+                .ok_or_else(invalid)?;
+        }
+
+        let mut start = bytes.len() - r.remaining().len();
+        let mut sections = lens.into_iter().map(|len| {
+            let end = start.checked_add(len).filter(|end| *end <= bytes.len())?;
+            let section = Section {
+                data: Arc::clone(&data),
+                start,
+                end,
+            };
+            start = end;
+            Some(section)
+        });
+        let mut next = || sections.next().flatten().ok_or_else(invalid);
+        let (words, records, stem_ends, stem_data) = (next()?, next()?, next()?, next()?);
+
+        if stem_ends.as_ref().len() % 4 != 0 {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            words: Map::new(words).map_err(|_| invalid())?,
+            records,
+            stem_ends,
+            stem_data,
+            parsed_config: Box::new(cfg),
+        })
+    }
+
+    /// Check that an entire string contains only words that are spelled correctly,
+    /// returns `true` if so. See [`Dictionary::check`].
+    #[inline]
+    pub fn check(&self, input: &str) -> bool {
+        word_splitter(input, self.parsed_config.word_chars()).all(|(_, w)| self.check_word(w))
+    }
+
+    /// Check that a single word is spelled correctly, returns `true` if so. See
+    /// [`Dictionary::check_word`].
+    #[inline]
+    pub fn check_word(&self, word: &str) -> bool {
+        self.find(word).is_some()
+    }
+
+    /// Check words in a string, returning a list of the start and end indices of any
+    /// incorrect words. See [`Dictionary::check_indices`].
+    #[inline]
+    pub fn check_indices<'a: 'd, 'd>(
+        &'d self,
+        input: &'a str,
+    ) -> impl Iterator<Item = (usize, &'a str)> + 'd {
+        word_splitter(input, self.parsed_config.word_chars())
+            .filter(|(_idx, w)| !self.check_word(w))
+    }
+
+    /// Return the stems of a word if it is correct, `None` otherwise. Output conversions
+    /// (`OCONV`) are applied. See [`WordEntry::stems`](super::WordEntry::stems).
+    #[inline]
+    pub fn stems(&self, word: &str) -> Option<Vec<Cow<'_, str>>> {
+        let record = self.find(word)?;
+        let ret = record
+            .stem_indices()
+            .filter_map(|idx| self.stem(idx))
+            .map(|stem| self.parsed_config.convert_output(stem))
+            .collect();
+        Some(ret)
+    }
+
+    /// The number of words in the dictionary, including forbidden words
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if the dictionary contains no words
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Find the record of a correct word, trying without a trailing period if needed
+    fn find(&self, word: &str) -> Option<Record<'_>> {
+        self.find_inner(word)
+            .or_else(|| strip_period(word).and_then(|w| self.find_inner(w)))
+    }
+
+    /// Same lookup rules as [`Dictionary::check_word`], without handling trailing periods
+    fn find_inner(&self, word: &str) -> Option<Record<'_>> {
+        let normalized = self.parsed_config.normalize_input(word);
+        let lookup = normalized.as_ref();
+        let lower = lookup.to_lowercase();
+
+        let exact = self.record(lookup);
+        let lowered = self.record(&lower);
+        if exact.into_iter().chain(lowered).any(|r| r.has(FORBID)) {
+            return None;
+        }
+
+        let found = exact
+            .filter(|r| r.has(ACCEPT))
+            .or_else(|| lowered.filter(|r| r.has(ACCEPT)))?;
+
+        if found.has(WARN) && self.parsed_config.forbid_warn_words() {
+            None
+        } else {
+            Some(found)
+        }
+    }
+
+    /// Look up the record for an exact word
+    fn record(&self, word: &str) -> Option<Record<'_>> {
+        let offset = self.words.get(word)?.try_into().ok()?;
+        let bytes = self.records.as_ref().get(offset..)?;
+        let (&attrs, rest) = bytes.split_first()?;
+        let count: usize = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?)
+            .try_into()
+            .ok()?;
+
+        Some(Record {
+            attrs,
+            stems: rest.get(4..4 + count.checked_mul(4)?)?,
+        })
+    }
+
+    /// Get a stem by index
+    fn stem(&self, idx: usize) -> Option<&str> {
+        let ends = self.stem_ends.as_ref();
+        let end_at = |i: usize| -> Option<usize> {
+            let bytes = ends.get(i * 4..i * 4 + 4)?;
+            u32::from_le_bytes(bytes.try_into().ok()?).try_into().ok()
+        };
+
+        let start = match idx.checked_sub(1) {
+            Some(prev) => end_at(prev)?,
+            None => 0,
+        };
+        let bytes = self.stem_data.as_ref().get(start..end_at(idx)?)?;
+        std::str::from_utf8(bytes).ok()
+    }
+}
+
+impl<D: AsRef<[u8]>> fmt::Debug for CompactDictionary<D> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactDictionary")
+            .field("len", &self.words.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A range of the shared dictionary data
+struct Section<D> {
+    data: Arc<D>,
+    start: usize,
+    end: usize,
+}
+
+impl<D: AsRef<[u8]>> AsRef<[u8]> for Section<D> {
+    fn as_ref(&self) -> &[u8] {
+        &(*self.data).as_ref()[self.start..self.end]
+    }
+}
+
+/// Information about a single word
+#[derive(Clone, Copy, Debug)]
+struct Record<'a> {
+    attrs: u8,
+    /// Packed `u32` stem indices
+    stems: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    fn has(self, attr: u8) -> bool {
+        self.attrs & attr != 0
+    }
+
+    fn stem_indices(self) -> impl Iterator<Item = usize> + 'a {
+        self.stems
+            .chunks_exact(4)
+            .filter_map(|chunk| u32::from_le_bytes(chunk.try_into().ok()?).try_into().ok())
+    }
+}
+
+/// Serialize a dictionary that was built from `cfg_src`. Only the wordlists are used, so
+/// the dictionary should not use [`Strategy::Lazy`](super::Strategy::Lazy).
+pub(super) fn write(dict: &Dictionary, cfg_src: &str) -> Vec<u8> {
+    let mut stem_ids: HashMap<&str, u32> = HashMap::new();
+    let mut stems: Vec<&str> = Vec::new();
+    // Sorted, as required to build the FST
+    let mut words: BTreeMap<&str, (u8, Vec<u32>)> = BTreeMap::new();
+
+    for (wordlist, attrs) in [
+        (&dict.wordlist, ACCEPT),
+        (&dict.wordlist_nosuggest, ACCEPT | NOSUGGEST),
+        (&dict.wordlist_forbidden, FORBID),
+    ] {
+        for (word, metas) in &wordlist.0 {
+            let entry = words.entry(word).or_default();
+            entry.0 |= attrs;
+            if attrs & ACCEPT == 0 {
+                continue;
+            }
+
+            for stem in meta_stems(metas) {
+                let id = *stem_ids.entry(stem).or_insert_with(|| {
+                    stems.push(stem);
+                    u32::try_from(stems.len() - 1).expect("too many stems")
+                });
+                if !entry.1.contains(&id) {
+                    entry.1.push(id);
+                }
+            }
+        }
+    }
+
+    let mut fst = MapBuilder::memory();
+    let mut records: Vec<u8> = Vec::new();
+    let mut record_offsets: HashMap<Vec<u8>, u64> = HashMap::new();
+
+    for (word, (mut attrs, ids)) in words {
+        for (set, attr) in [(&dict.warn, WARN), (&dict.substandard, SUBSTANDARD)] {
+            if set.contains(word) {
+                attrs |= attr;
+            }
+        }
+
+        let mut record = vec![attrs];
+        record.extend_from_slice(&len_u32(ids.len()).to_le_bytes());
+        for id in ids {
+            record.extend_from_slice(&id.to_le_bytes());
+        }
+
+        let offset = *record_offsets.entry(record).or_insert_with_key(|record| {
+            let offset = records.len() as u64;
+            records.extend_from_slice(record);
+            offset
+        });
+        fst.insert(word, offset)
+            .expect("words are sorted and unique");
+    }
+
+    let fst = fst.into_inner().expect("writing to memory cannot fail");
+
+    let mut stem_ends: Vec<u8> = Vec::with_capacity(stems.len() * 4);
+    let mut stem_data: Vec<u8> = Vec::new();
+    for stem in stems {
+        stem_data.extend_from_slice(stem.as_bytes());
+        stem_ends.extend_from_slice(&len_u32(stem_data.len()).to_le_bytes());
+    }
+
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    for s in [env!("CARGO_PKG_VERSION"), cfg_src] {
+        buf.extend_from_slice(&len_u32(s.len()).to_le_bytes());
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    let sections = [fst, records, stem_ends, stem_data];
+    for section in &sections {
+        buf.extend_from_slice(&(section.len() as u64).to_le_bytes());
+    }
+    for section in &sections {
+        buf.extend_from_slice(section);
+    }
+
+    buf
+}
+
+fn len_u32(len: usize) -> u32 {
+    u32::try_from(len).expect("dictionary section too large")
+}
@@ -43,6 +43,11 @@ impl Meta {
     pub fn source(&self) -> &Source {
         &self.source
     }
+
+    /// The stem this meta was created from, ignoring morph info
+    pub(crate) fn stem_rc(&self) -> &Arc<str> {
+        &self.stem
+    }
 }
 
 /// Source information
@@ -99,6 +104,14 @@ impl PersonalMeta {
     pub fn new(friend: Option<Arc<str>>, morph: Vec<Arc<MorphInfo>>) -> Self {
         Self { friend, morph }
     }
+
+    pub(crate) fn friend(&self) -> Option<&Arc<str>> {
+        self.friend.as_ref()
+    }
+
+    pub(crate) fn morph(&self) -> &[Arc<MorphInfo>] {
+        &self.morph
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,264 @@
+//! Check text that mixes several languages
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::rules_apply::word_splitter;
+use super::Dictionary;
+use crate::error::Error;
+
+/// A set of dictionaries keyed by language code, used to check documents that mix
+/// languages.
+///
+/// Dictionaries are keyed by their `LANG` setting, or by an explicit code. Text can be
+/// checked by giving the language of each span with
+/// [`check_spans`](Self::check_spans), or by letting each sentence be checked with the
+/// dictionary that accepts the most of its words with [`check`](Self::check).
+///
+/// ```
+/// use zspell::{DictBuilder, MultiDictionary};
+///
+/// let en = DictBuilder::new()
+///     .config_str("LANG en_US")
+///     .dict_str("the\nquote\nsays\nso")
+///     .build()
+///     .unwrap();
+///
+/// let de = DictBuilder::new()
+///     .config_str("LANG de_DE")
+///     .dict_str("alles\nist\ngut")
+///     .build()
+///     .unwrap();
+///
+/// let mut multi = MultiDictionary::new();
+/// multi.insert(en);
+/// multi.insert(de);
+///
+/// let errors = multi.check("The quote says so. Alles ist gutt.");
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].word(), "gutt");
+/// assert_eq!(errors[0].lang(), "de_DE");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MultiDictionary {
+    /// Dictionaries in the order they were added. The first one wins ties.
+    dicts: Vec<(Box<str>, Dictionary)>,
+}
+
+impl MultiDictionary {
+    /// Create an empty set of dictionaries
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a dictionary keyed by the language from its affix file (`LANG`). If a
+    /// dictionary already exists for that language, it is replaced and returned.
+    ///
+    /// Dictionaries without `LANG` are keyed by an empty string; use
+    /// [`insert_as`](Self::insert_as) to give them a code.
+    #[inline]
+    pub fn insert(&mut self, dict: Dictionary) -> Option<Dictionary> {
+        let lang = dict.parsed_config.lang().to_owned();
+        self.insert_as(&lang, dict)
+    }
+
+    /// Add a dictionary with the given language code, ignoring its `LANG`. If a
+    /// dictionary already exists for that language, it is replaced and returned.
+    #[inline]
+    pub fn insert_as(&mut self, lang: &str, dict: Dictionary) -> Option<Dictionary> {
+        if let Some((_, existing)) = self.dicts.iter_mut().find(|(l, _)| l.as_ref() == lang) {
+            return Some(std::mem::replace(existing, dict));
+        }
+        self.dicts.push((lang.into(), dict));
+        None
+    }
+
+    /// Remove the dictionary for a language and return it
+    #[inline]
+    pub fn remove(&mut self, lang: &str) -> Option<Dictionary> {
+        let idx = self.dicts.iter().position(|(l, _)| l.as_ref() == lang)?;
+        Some(self.dicts.remove(idx).1)
+    }
+
+    /// Get the dictionary for a language
+    #[inline]
+    pub fn get(&self, lang: &str) -> Option<&Dictionary> {
+        self.dicts
+            .iter()
+            .find_map(|(l, dict)| (l.as_ref() == lang).then_some(dict))
+    }
+
+    /// Get the dictionary for a language mutably, e.g. to add words at runtime
+    #[inline]
+    pub fn get_mut(&mut self, lang: &str) -> Option<&mut Dictionary> {
+        self.dicts
+            .iter_mut()
+            .find_map(|(l, dict)| (l.as_ref() == lang).then_some(dict))
+    }
+
+    /// Language codes of all dictionaries, in the order they were added
+    #[inline]
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.dicts.iter().map(|(l, _)| l.as_ref())
+    }
+
+    /// The language whose dictionary accepts the most words in `text`. Ties go to the
+    /// dictionary that was added first. Returns `None` if there are no dictionaries.
+    #[inline]
+    pub fn best_fit(&self, text: &str) -> Option<&str> {
+        self.best_fit_inner(text).map(|(lang, _)| lang)
+    }
+
+    /// Check each sentence with the language that accepts the most of its words, and
+    /// return the incorrect words tagged with the language that was used.
+    #[inline]
+    pub fn check<'d, 's>(&'d self, input: &'s str) -> Vec<Misspelling<'d, 's>> {
+        let mut ret = Vec::new();
+        self.check_auto(input, 0, &mut ret);
+        ret
+    }
+
+    /// Check text with a known language for some spans. Each span is a byte range in
+    /// `input` and a language code. Text outside of the spans is checked per sentence
+    /// as with [`check`](Self::check). Spans may be given in any order.
+    ///
+    /// Incorrect words are returned in order, tagged with the language that was used.
+    ///
+    /// ```
+    /// use zspell::{DictBuilder, MultiDictionary};
+    ///
+    /// let en = DictBuilder::new()
+    ///     .config_str("")
+    ///     .dict_str("she\nsaid")
+    ///     .build()
+    ///     .unwrap();
+    /// let fr = DictBuilder::new()
+    ///     .config_str("")
+    ///     .dict_str("oui")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut multi = MultiDictionary::new();
+    /// multi.insert_as("en", en);
+    /// multi.insert_as("fr", fr);
+    ///
+    /// let input = "She said oui, she said";
+    /// let errors = multi.check_spans(input, &[(9..12, "en")]).unwrap();
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].index(), 9);
+    /// assert_eq!(errors[0].lang(), "en");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no dictionary for a span's language, or if a span
+    /// is out of bounds, not on a `char` boundary, or overlaps another span.
+    #[inline]
+    pub fn check_spans<'d, 's>(
+        &'d self,
+        input: &'s str,
+        spans: &[(Range<usize>, &str)],
+    ) -> Result<Vec<Misspelling<'d, 's>>, Error> {
+        let mut sorted: Vec<(Range<usize>, &'d str, &'d Dictionary)> = Vec::new();
+        for (range, lang) in spans {
+            let (lang, dict) = self
+                .dicts
+                .iter()
+                .find(|(l, _)| l.as_ref() == *lang)
+                .ok_or_else(|| Error::UnknownLanguage((*lang).to_owned()))?;
+            sorted.push((range.clone(), lang, dict));
+        }
+        sorted.sort_by_key(|(range, _, _)| range.start);
+
+        let mut ret = Vec::new();
+        let mut pos = 0;
+        for (range, lang, dict) in sorted {
+            let text = input
+                .get(range.clone())
+                .filter(|_| range.start >= pos)
+                .ok_or_else(|| Error::InvalidSpan(range.clone()))?;
+            if range.start > pos {
+                self.check_auto(&input[pos..range.start], pos, &mut ret);
+            }
+            Self::check_with(text, range.start, lang, dict, &mut ret);
+            pos = range.end;
+        }
+        self.check_auto(&input[pos..], pos, &mut ret);
+
+        Ok(ret)
+    }
+
+    /// Find the best dictionary for a string
+    fn best_fit_inner(&self, text: &str) -> Option<(&str, &Dictionary)> {
+        let mut best: Option<(&str, &Dictionary, usize)> = None;
+        for (lang, dict) in &self.dicts {
+            let count = word_splitter(text, dict.parsed_config.word_chars())
+                .filter(|(_, w)| dict.check_word(w))
+                .count();
+            if best.map_or(true, |(_, _, best_count)| count > best_count) {
+                best = Some((lang, dict, count));
+            }
+        }
+        best.map(|(lang, dict, _)| (lang, dict))
+    }
+
+    /// Check each sentence with its best dictionary. `offset` is the position of `text`
+    /// in the input.
+    fn check_auto<'d, 's>(
+        &'d self,
+        text: &'s str,
+        offset: usize,
+        dest: &mut Vec<Misspelling<'d, 's>>,
+    ) {
+        for (idx, sentence) in text.split_sentence_bound_indices() {
+            if let Some((lang, dict)) = self.best_fit_inner(sentence) {
+                Self::check_with(sentence, offset + idx, lang, dict, dest);
+            }
+        }
+    }
+
+    /// Check text with a single dictionary
+    fn check_with<'d, 's>(
+        text: &'s str,
+        offset: usize,
+        lang: &'d str,
+        dict: &'d Dictionary,
+        dest: &mut Vec<Misspelling<'d, 's>>,
+    ) {
+        dest.extend(dict.check_indices(text).map(|(idx, word)| Misspelling {
+            word,
+            index: offset + idx,
+            lang,
+        }));
+    }
+}
+
+/// An incorrect word found by [`MultiDictionary`], with the language it was checked in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Misspelling<'dict, 'word> {
+    word: &'word str,
+    index: usize,
+    lang: &'dict str,
+}
+
+impl<'dict, 'word> Misspelling<'dict, 'word> {
+    /// The incorrect word
+    #[inline]
+    pub fn word(&self) -> &'word str {
+        self.word
+    }
+
+    /// The byte index of the word in the input string
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The language code of the dictionary the word was checked with
+    #[inline]
+    pub fn lang(&self) -> &'dict str {
+        self.lang
+    }
+}
@@ -0,0 +1,227 @@
+//! Compress a list of words into stems with affix flags, like Hunspell's `munch`
+
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::sync::Arc;
+
+use hashbrown::HashSet;
+
+use super::rule::AfxRule;
+use super::rules_apply::{create_affixed_word_map, AfxContext};
+use super::{Dictionary, Flag, FlagValue, WordList};
+use crate::affix::FlagType;
+
+/// Find a small set of stems and flags that create exactly the words in `words`, and
+/// write them as a dictionary file.
+pub(super) fn munch(dict: &Dictionary, words: &str) -> String {
+    let normalized: Vec<Cow<str>> = words
+        .lines()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(|w| dict.parsed_config.normalize_input(w))
+        .collect();
+    let targets: HashSet<&str> = normalized.iter().map(AsRef::as_ref).collect();
+
+    let rules: Vec<(Flag, &Arc<AfxRule>)> = dict
+        .affix_flags
+        .iter()
+        .filter_map(|(flag, value)| match value {
+            FlagValue::Rule(rule) => Some((*flag, rule)),
+            _ => None,
+        })
+        .collect();
+
+    // Each word may be a stem. Find the flags it can take without creating anything
+    // outside of the list, and which words those flags create. With `AF`, entries may
+    // only refer to an alias so one of those is chosen instead.
+    let aliases = dict.parsed_config.affix_aliases();
+    let mut candidates: BTreeMap<&str, (String, Vec<String>)> = BTreeMap::new();
+    for &stem in &targets {
+        let candidate = if aliases.is_empty() {
+            let (flags, created) = stem_flags(dict, stem, &rules, &targets);
+            (flags_to_str(dict.flag_type, &flags), created)
+        } else {
+            alias_flags(dict, stem, &aliases, &targets)
+        };
+        candidates.insert(stem, candidate);
+    }
+
+    // Greedy set cover: repeatedly take the stem that creates the most words that are
+    // not yet covered. Counts only decrease, so stale heap entries are rechecked when
+    // they are popped.
+    let mut uncovered = targets.clone();
+    let mut heap: BinaryHeap<(usize, Reverse<&str>)> = candidates
+        .iter()
+        .map(|(stem, (_, created))| (created.len() + 1, Reverse(*stem)))
+        .collect();
+    let mut selected: Vec<(&str, &str)> = Vec::new();
+
+    while let Some((count, Reverse(stem))) = heap.pop() {
+        if uncovered.is_empty() {
+            break;
+        }
+
+        let (flags, created) = &candidates[stem];
+        let covers = |word: &str| uncovered.contains(word);
+        let current = usize::from(covers(stem)) + created.iter().filter(|w| covers(w)).count();
+
+        if current == 0 {
+            continue;
+        }
+        if current < count {
+            heap.push((current, Reverse(stem)));
+            continue;
+        }
+
+        uncovered.remove(stem);
+        for word in created {
+            uncovered.remove(word.as_str());
+        }
+        selected.push((stem, flags));
+    }
+
+    selected.sort_unstable_by_key(|(stem, _)| *stem);
+
+    let mut ret = format!("{}\n", selected.len());
+    for (stem, flags) in selected {
+        ret.push_str(&stem.replace('/', "\\/"));
+        if !flags.is_empty() {
+            ret.push('/');
+            ret.push_str(flags);
+        }
+        ret.push('\n');
+    }
+
+    ret
+}
+
+/// Write flags as they appear in a dictionary entry
+fn flags_to_str(flag_type: FlagType, flags: &[Flag]) -> String {
+    let mut ret = String::new();
+    for (idx, flag) in flags.iter().enumerate() {
+        if idx > 0 && flag_type == FlagType::Number {
+            ret.push(',');
+        }
+        ret.push_str(&flag_type.flag_to_str(*flag));
+    }
+    ret
+}
+
+/// Greedily pick the flags a stem can take, keeping each flag that creates more words
+/// without creating anything outside of `targets`. Returns the flags and the words
+/// they create.
+///
+/// A flag's words only depend on the other flags if a prefix and a suffix combine, so
+/// each flag is expanded once on its own and combined forms are only created for
+/// pairs of rules that allow it.
+fn stem_flags(
+    dict: &Dictionary,
+    stem: &str,
+    rules: &[(Flag, &Arc<AfxRule>)],
+    targets: &HashSet<&str>,
+) -> (Vec<Flag>, Vec<String>) {
+    let mut flags: Vec<(Flag, &Arc<AfxRule>)> = Vec::new();
+    let mut created: HashSet<String> = HashSet::new();
+
+    for &(flag, rule) in rules {
+        let mut words = expand(dict, stem, &[rule]);
+        for &(_, other) in &flags {
+            if combines(rule, other) {
+                words.extend(expand(dict, stem, &[rule, other]));
+            }
+        }
+
+        // Only keep flags that create more words, all of which are in the list
+        let adds_words = words.iter().any(|w| !created.contains(w));
+        if adds_words && words.iter().all(|w| targets.contains(w.as_str())) {
+            flags.push((flag, rule));
+            created.extend(words);
+        }
+    }
+
+    let mut created: Vec<String> = created.into_iter().collect();
+    created.sort_unstable();
+    (flags.into_iter().map(|(flag, _)| flag).collect(), created)
+}
+
+/// Pick the `AF` alias whose rules create the most words without creating anything
+/// outside of `targets`. Returns the alias index and the words it creates. Aliases with
+/// flags other than affix rules are never chosen, since they would change the stem.
+fn alias_flags(
+    dict: &Dictionary,
+    stem: &str,
+    aliases: &[Vec<Flag>],
+    targets: &HashSet<&str>,
+) -> (String, Vec<String>) {
+    let mut ret = (String::new(), Vec::new());
+
+    for (idx, flags) in aliases.iter().enumerate() {
+        let rules: Option<Vec<&Arc<AfxRule>>> = flags
+            .iter()
+            .map(|flag| match dict.affix_flags.get(flag) {
+                Some(FlagValue::Rule(rule)) => Some(rule),
+                _ => None,
+            })
+            .collect();
+        let Some(rules) = rules else {
+            continue;
+        };
+
+        let mut words = Vec::new();
+        for (i, rule) in rules.iter().enumerate() {
+            words.extend(expand(dict, stem, &[rule]));
+            for other in rules[..i].iter().filter(|other| combines(rule, other)) {
+                words.extend(expand(dict, stem, &[rule, other]));
+            }
+        }
+        words.sort_unstable();
+        words.dedup();
+
+        if words.len() > ret.1.len() && words.iter().all(|w| targets.contains(w.as_str())) {
+            ret = ((idx + 1).to_string(), words);
+        }
+    }
+
+    ret
+}
+
+/// Whether a prefix and a suffix rule may both apply to the same stem
+fn combines(rule: &AfxRule, other: &AfxRule) -> bool {
+    rule.is_pfx() != other.is_pfx() && rule.can_combine() && other.can_combine()
+}
+
+/// All words created by applying `rules` to a stem, not including the stem itself. This
+/// also checks that the combination of affixes is allowed.
+fn expand(dict: &Dictionary, stem: &str, rules: &[&Arc<AfxRule>]) -> Vec<String> {
+    let (pfx_rules, sfx_rules): (Vec<_>, Vec<_>) = rules.iter().partition(|r| r.is_pfx());
+
+    let ctx = AfxContext {
+        affix_flags: &dict.affix_flags,
+        rule_flags: &dict.rule_flags,
+        complex_prefixes: dict.parsed_config.complex_prefixes(),
+    };
+
+    let stem: Arc<str> = stem.into();
+    let mut words = WordList::new();
+    let mut substandard_words = WordList::new();
+    create_affixed_word_map(
+        &stem,
+        &pfx_rules,
+        &sfx_rules,
+        &ctx,
+        None,
+        &mut words,
+        &mut substandard_words,
+    );
+
+    let mut ret: Vec<String> = words
+        .0
+        .into_keys()
+        .chain(substandard_words.0.into_keys())
+        .map(Into::into)
+        .collect();
+    ret.sort_unstable();
+    ret.dedup();
+    ret
+}
@@ -1,12 +1,13 @@
 //! Parse a dict file
 
+use std::borrow::Cow;
 use std::sync::Arc;
 
 use super::Flag;
-use crate::affix::FlagType;
 use crate::error::ParseError;
 use crate::helpers::convertu32;
 use crate::morph::MorphInfo;
+use crate::ParsedCfg;
 
 /// Represent a single line in a dictionary file
 ///
@@ -38,23 +39,32 @@ impl DictEntry {
     }
 
     /// Create a `DictEntry` from a single line in a `.dic` file. Does not strip comments.
-    fn parse_single(value: &str, flag_type: FlagType, line_num: u32) -> Result<Self, ParseError> {
-        let (stem, flagstr, morphstr) = separate_into_parts(value);
+    ///
+    /// Flags and morph info are resolved using `AF` and `AM` aliases, if the config has
+    /// any.
+    fn parse_single(value: &str, cfg: &ParsedCfg, line_num: u32) -> Result<Self, ParseError> {
+        let (mut stem, flagstr, mut morphstr) = separate_into_parts(value);
+
+        // An entry without flags may still have a numeric `AM` reference
+        if flagstr.is_none() && morphstr.is_empty() && cfg.has_morph_alias() {
+            if let Some((word, alias)) = stem.trim().rsplit_once(char::is_whitespace) {
+                if alias.parse::<usize>().is_ok() {
+                    (stem, morphstr) = (word, alias);
+                }
+            }
+        }
 
         let flags: Vec<Flag> = match flagstr {
-            Some(s) => flag_type
-                .parse_str(s.trim())
+            Some(s) => cfg
+                .parse_flags(s.trim())
                 .map_err(|e| ParseError::new_nocol(e, s, line_num))?,
             None => Vec::new(),
         };
-        let morph = MorphInfo::many_from_str(morphstr.trim())
-            .map(Arc::new)
-            .collect();
-        let ret = Self {
-            stem: stem.trim().into(),
-            flags,
-            morph,
-        };
+        let morph = cfg
+            .parse_morphs(morphstr.trim())
+            .map_err(|e| ParseError::new_nocol(e, morphstr, line_num))?;
+        let stem = unescape_slash(stem.trim()).as_ref().into();
+        let ret = Self { stem, flags, morph };
         Ok(ret)
     }
 
@@ -64,32 +74,56 @@ impl DictEntry {
     ///
     /// Returns an error if any entry is incorrect.
     #[inline]
-    #[allow(clippy::option_if_let_else)]
-    pub fn parse_all(input: &str, flag_type: FlagType) -> Result<Vec<DictEntry>, ParseError> {
-        // Ignore empty lines and
-        let mut lines_iter = extract_content(input);
-        let lines_backup = lines_iter.clone();
-
-        let Some(first) = lines_iter.next() else {
-            return Ok(Vec::new());
-        };
-
-        // Try to parse the first line as an integer; if not, ignore it
-        let (mut ret, start) = if let Ok(cap) = first.parse::<usize>() {
-            (Vec::with_capacity(cap), 2)
-        } else {
-            lines_iter = lines_backup;
-            (Vec::new(), 1)
-        };
+    pub fn parse_all(input: &str, cfg: &ParsedCfg) -> Result<Vec<DictEntry>, ParseError> {
+        let (lines_iter, capacity, start) = entry_lines(input);
+        let mut ret = Vec::with_capacity(capacity);
 
         for (i, line) in lines_iter.enumerate() {
-            ret.push(
-                DictEntry::parse_single(line, flag_type, convertu32(i + start))
-                    .map_err(|e| e.add_offset_ret(i + start, 0))?,
-            );
+            ret.push(DictEntry::parse_line(line, cfg, i + start)?);
         }
         Ok(ret)
     }
+
+    /// Parse a complete dictionary file using multiple threads. The result, including
+    /// which error is returned, is the same as [`parse_all`](Self::parse_all).
+    #[cfg(feature = "parallel")]
+    pub(crate) fn parse_all_par(
+        input: &str,
+        cfg: &ParsedCfg,
+    ) -> Result<Vec<DictEntry>, ParseError> {
+        use rayon::prelude::*;
+
+        let (lines_iter, _, start) = entry_lines(input);
+        let lines: Vec<&str> = lines_iter.collect();
+        let parsed: Vec<Result<DictEntry, ParseError>> = lines
+            .par_iter()
+            .enumerate()
+            .map(|(i, line)| DictEntry::parse_line(line, cfg, i + start))
+            .collect();
+
+        // Collect sequentially so we return the first error
+        parsed.into_iter().collect()
+    }
+
+    /// Parse a line, adding its line number to any error
+    fn parse_line(line: &str, cfg: &ParsedCfg, line_num: usize) -> Result<Self, ParseError> {
+        DictEntry::parse_single(line, cfg, convertu32(line_num))
+            .map_err(|e| e.add_offset_ret(line_num, 0))
+    }
+}
+
+/// Get the lines of a dictionary file that contain entries, the number of entries
+/// given in the header (if any), and the line number of the first entry.
+fn entry_lines(input: &str) -> (impl Iterator<Item = &str> + Clone, usize, usize) {
+    // Ignore empty lines and
+    let mut lines_iter = extract_content(input);
+    let lines_backup = lines_iter.clone();
+
+    // Try to parse the first line as an integer; if not, ignore it
+    match lines_iter.next().map(str::parse::<usize>) {
+        Some(Ok(cap)) => (lines_iter, cap, 2),
+        _ => (lines_backup, 0, 1),
+    }
 }
 
 /// Represent an entry from a personal dictionary
@@ -138,8 +172,8 @@ impl PersonalEntry {
         let morph = MorphInfo::many_from_str(morphstr).collect();
 
         Self {
-            stem: stem.trim().into(),
-            friend: friend.map(|f| f.trim().into()),
+            stem: unescape_slash(stem.trim()).as_ref().into(),
+            friend: friend.map(|f| unescape_slash(f.trim()).as_ref().into()),
             morph,
             forbid,
         }
@@ -158,10 +192,17 @@ fn separate_into_parts(value: &str) -> (&str, Option<&str>, &str) {
 
     let value = value.split_once('#').unwrap_or((value, "")).0;
 
+    // Flags start at the first `/` that is not escaped as `\/`
+    let flag_sep = value
+        .match_indices('/')
+        .map(|(idx, _)| idx)
+        .find(|&idx| !value[..idx].ends_with('\\'));
+
     // Split out the sections
-    if let Some((word, rest)) = value.split_once('/') {
+    if let Some(idx) = flag_sep {
         // Easy case, we have an affix and can split on `/`. Then just split the first
         // whitespace to separate morph from the flags.
+        let (word, rest) = (&value[..idx], &value[idx + 1..]);
         stem = word;
         let (tmpflag, tmpmorph) = rest
             .split_once(|ch: char| ch.is_ascii_whitespace())
@@ -181,6 +222,15 @@ fn separate_into_parts(value: &str) -> (&str, Option<&str>, &str) {
     (stem, flagstr, morphstr)
 }
 
+/// A slash in a stem is escaped as `\/`, since a plain `/` starts its flags
+fn unescape_slash(stem: &str) -> Cow<'_, str> {
+    if stem.contains("\\/") {
+        Cow::Owned(stem.replace("\\/", "/"))
+    } else {
+        Cow::Borrowed(stem)
+    }
+}
+
 /// Extract nonempty lines that do not contain a comment
 fn extract_content(input: &str) -> impl Iterator<Item = &str> + Clone {
     input
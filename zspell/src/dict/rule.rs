@@ -1,9 +1,12 @@
 //! Implementation for a stored rule
 
+use std::borrow::Cow;
 use std::hash::Hash;
 use std::sync::Arc;
 
+use super::Flag;
 use crate::affix::{ParsedCfg, ParsedRuleGroup, RuleType};
+use crate::error::ParseError;
 use crate::helpers::ReWrapper;
 use crate::morph::MorphInfo;
 
@@ -12,6 +15,8 @@ use crate::morph::MorphInfo;
 pub struct AfxRule {
     kind: RuleType,
     can_combine: bool,
+    /// Allow stripping the entire stem, from `FULLSTRIP`
+    full_strip: bool,
     patterns: Vec<AfxRulePattern>,
 }
 
@@ -29,6 +34,7 @@ impl AfxRule {
         let mut ret = Self {
             kind,
             can_combine,
+            full_strip: false,
             patterns: affixes
                 .iter()
                 .map(|afx| AfxRulePattern::new(afx, None))
@@ -44,28 +50,51 @@ impl AfxRule {
     ///
     /// NOTE: returns a vec reference and `Self`'s morph vec will be empty!
     /// Needs construction wherever the Arc target is
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if continuation flags do not match the flag type
     // PERF: bench with & without vec reference instead of output
-    pub fn from_parsed_group(_cfg: &ParsedCfg, group: &ParsedRuleGroup) -> Self {
+    pub fn from_parsed_group(cfg: &ParsedCfg, group: &ParsedRuleGroup) -> Result<Self, ParseError> {
         let mut ret = Self {
             kind: group.kind,
             can_combine: group.can_combine,
+            full_strip: cfg.afx_full_strip(),
             patterns: Vec::with_capacity(group.rules.len()),
         };
 
         for rule in &group.rules {
             let morph_info: Vec<Arc<MorphInfo>> = rule.morph_info.clone();
+            let cont_flags = match &rule.cont_flags {
+                Some(flags) => cfg
+                    .parse_flags(flags)
+                    .map_err(|e| ParseError::new_nospan(e, flags))?,
+                None => Vec::new(),
+            };
 
-            ret.patterns.push(AfxRulePattern {
-                affix: rule.affix.as_str().into(),
-                condition: rule.condition.clone(),
+            // `IGNORE` characters never appear in stems, so remove them from the affix too
+            let strip = match rule.strip.as_deref().map(|s| cfg.strip_ignored(s)) {
+                Some(Cow::Owned(s)) if s.is_empty() => None,
+                Some(Cow::Owned(s)) => Some(s.into()),
                 // FIXME: `rule.strip.as_ref().map(Arc::clone)` is more accurate, but flagged by
                 // clippy
-                strip: rule.strip.clone(),
+                Some(Cow::Borrowed(_)) | None => rule.strip.clone(),
+            };
+
+            ret.patterns.push(AfxRulePattern {
+                affix: cfg.strip_ignored(&rule.affix).into(),
+                cont_flags,
+                condition: rule.condition.clone(),
+                strip,
                 morph_info,
             });
         }
 
-        ret
+        Ok(ret)
+    }
+
+    pub fn kind(&self) -> RuleType {
+        self.kind
     }
 
     pub fn is_pfx(&self) -> bool {
@@ -73,7 +102,7 @@ impl AfxRule {
     }
 
     pub fn is_sfx(&self) -> bool {
-        self.kind == RuleType::Prefix
+        self.kind == RuleType::Suffix
     }
 
     pub fn can_combine(&self) -> bool {
@@ -85,11 +114,29 @@ impl AfxRule {
     pub fn apply_patterns<'a>(
         &'a self,
         stem: &'a str,
+    ) -> impl Iterator<Item = (usize, String)> + 'a {
+        self.patterns.iter().enumerate().filter_map(|(idx, pat)| {
+            pat.apply_pattern(stem, self.kind, self.full_strip)
+                .map(|s| (idx, s))
+        })
+    }
+
+    /// The reverse of [`apply_patterns`](Self::apply_patterns): find patterns that could
+    /// have created `word`. Returns an iterator over the index of the pattern and the
+    /// word with the affix removed.
+    pub fn strip_patterns<'a>(
+        &'a self,
+        word: &'a str,
     ) -> impl Iterator<Item = (usize, String)> + 'a {
         self.patterns
             .iter()
             .enumerate()
-            .filter_map(|(idx, pat)| pat.apply_pattern(stem, self.kind).map(|s| (idx, s)))
+            .filter_map(move |(idx, pat)| {
+                let base = pat.strip_affix(word, self.kind)?;
+                // Conditions apply to the base word, so confirm by applying the pattern
+                let reapplied = pat.apply_pattern(&base, self.kind, self.full_strip)?;
+                (reapplied == word).then_some((idx, base))
+            })
     }
 
     pub(crate) fn patterns(&self) -> &[AfxRulePattern] {
@@ -101,6 +148,8 @@ impl AfxRule {
 #[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
 pub struct AfxRulePattern {
     affix: Box<str>,
+    /// Flags that apply to the affixed word, e.g. rules for twofold affixes
+    cont_flags: Vec<Flag>,
     /// Condition to be met to apply this rule.
     condition: Option<ReWrapper>,
     /// Characters to strip
@@ -115,6 +164,7 @@ impl AfxRulePattern {
     pub fn new(afx: &str, strip: Option<&str>) -> Self {
         Self {
             affix: afx.into(),
+            cont_flags: Vec::new(),
             condition: None,
             strip: strip.map(Into::into),
             morph_info: Vec::new(),
@@ -141,11 +191,42 @@ impl AfxRulePattern {
         &self.morph_info
     }
 
+    pub(crate) fn cont_flags(&self) -> &[Flag] {
+        &self.cont_flags
+    }
+
+    /// Unless `full_strip` is set, a rule may not strip the entire stem
+    fn check_strip(&self, s: &str, full_strip: bool) -> bool {
+        full_strip || self.strip.as_ref().map_or(true, |sc| sc.as_ref() != s)
+    }
+
+    /// Remove this pattern's affix from a word and restore the stripped characters.
+    /// Returns `None` if the affix does not match or nothing would be left.
+    fn strip_affix(&self, s: &str, kind: RuleType) -> Option<String> {
+        let strip = self.strip.as_deref().unwrap_or_default();
+        let base = match kind {
+            RuleType::Prefix => {
+                let rest = s.strip_prefix(self.affix.as_ref())?;
+                let mut working = String::with_capacity(strip.len() + rest.len());
+                working.push_str(strip);
+                working.push_str(rest);
+                working
+            }
+            RuleType::Suffix => {
+                let mut working = s.strip_suffix(self.affix.as_ref())?.to_owned();
+                working.push_str(strip);
+                working
+            }
+        };
+
+        (!base.is_empty()).then_some(base)
+    }
+
     // Verify the match condition and apply this rule
     #[allow(clippy::option_if_let_else)]
-    fn apply_pattern(&self, s: &str, kind: RuleType) -> Option<String> {
+    fn apply_pattern(&self, s: &str, kind: RuleType, full_strip: bool) -> Option<String> {
         // No return if condition doesn't match
-        if !self.check_condition(s) {
+        if !self.check_condition(s) || !self.check_strip(s, full_strip) {
             return None;
         }
 
@@ -1,125 +1,305 @@
 //! Tools to forward generate a wordlist. That is, take an initial wordlist and a
 //! group of rules, and expand the wordlist by applying those rules.
 
+use std::collections::BTreeMap;
+use std::iter::Peekable;
 use std::sync::Arc;
 
-use unicode_segmentation::UnicodeSegmentation;
+use unicode_segmentation::{UWordBoundIndices, UnicodeSegmentation};
 
 use super::rule::AfxRule;
-use super::WordList;
+use super::{Flag, FlagValue, WordList};
+use crate::affix::RuleType;
 use crate::dict::meta::{Meta, Source};
 
-/// A rule that may be combined with another
-type PossibleCombination<'a> = (String, &'a Arc<AfxRule>, usize);
+/// A rule and the index of the pattern within it that was applied
+pub(super) type Applied<'a> = (&'a Arc<AfxRule>, usize);
+
+/// Flag information needed to apply continuation classes
+pub(super) struct AfxContext<'a> {
+    /// Flags and rules that apply to affixes
+    pub affix_flags: &'a BTreeMap<Flag, FlagValue>,
+    /// Flags that apply to other flags or rules
+    pub rule_flags: &'a BTreeMap<Flag, FlagValue>,
+    /// Use twofold prefixes rather than twofold suffixes (`COMPLEXPREFIXES`)
+    pub complex_prefixes: bool,
+}
+
+impl<'a> AfxContext<'a> {
+    /// Rules of a given kind referenced by continuation flags
+    pub fn cont_rules<'b>(
+        &'b self,
+        applied: &'b [Applied<'a>],
+        kind: RuleType,
+    ) -> impl Iterator<Item = &'a Arc<AfxRule>> + 'b {
+        let affix_flags = self.affix_flags;
+        applied
+            .iter()
+            .flat_map(|(rule, pat_idx)| rule.patterns()[*pat_idx].cont_flags())
+            .filter_map(move |flag| match affix_flags.get(flag) {
+                Some(FlagValue::Rule(rule)) if rule.kind() == kind => Some(rule),
+                _ => None,
+            })
+    }
+
+    /// Check whether an applied pattern has a continuation flag of a given type
+    fn cont_has(&self, (rule, pat_idx): &Applied, value: &FlagValue) -> bool {
+        rule.patterns()[*pat_idx].cont_flags().iter().any(|flag| {
+            self.affix_flags.get(flag) == Some(value) || self.rule_flags.get(flag) == Some(value)
+        })
+    }
+
+    /// Check that a combination of affixes may stand as a word. At least one affix must
+    /// not have `NEEDAFFIX`, and `CIRCUMFIX` affixes must come as a prefix/suffix pair.
+    pub fn form_allowed(&self, applied: &[Applied]) -> bool {
+        let independent = applied
+            .iter()
+            .any(|app| !self.cont_has(app, &FlagValue::AfxNeeded));
+        let circumfix = |kind: RuleType| {
+            applied
+                .iter()
+                .any(|app| app.0.kind() == kind && self.cont_has(app, &FlagValue::AfxCircumfix))
+        };
+
+        independent && circumfix(RuleType::Prefix) == circumfix(RuleType::Suffix)
+    }
+
+    /// Check whether any applied affix is `SUBSTANDARD`
+    pub fn is_substandard(&self, applied: &[Applied]) -> bool {
+        applied
+            .iter()
+            .any(|app| self.cont_has(app, &FlagValue::AfxSubstandard))
+    }
+
+    /// Words created with any `SUBSTANDARD` affix get stored separately
+    fn select_dest<'d>(
+        &self,
+        applied: &[Applied],
+        dest: &'d mut WordList,
+        substandard_dest: &'d mut WordList,
+    ) -> &'d mut WordList {
+        if self.is_substandard(applied) {
+            substandard_dest
+        } else {
+            dest
+        }
+    }
+}
 
 /// For a given stem, find all prefix and suffix rules that can apply, and store them
 /// to a wordlist.
 ///
-/// Also finds words
+/// Suffixes may be twofold (a second suffix from the continuation flags of the first),
+/// or prefixes with `COMPLEXPREFIXES`. The other affix type may only be applied once.
+///
+/// Words created with a `SUBSTANDARD` affix are stored to `substandard_dest` rather
+/// than `dest`.
 #[allow(clippy::similar_names)] // thinks pfx and sfx are too similar
-pub(super) fn create_affixed_word_map(
+pub(super) fn create_affixed_word_map<'a>(
     stem: &Arc<str>,
-    pfx_rules: &[&Arc<AfxRule>],
-    sfx_rules: &[&Arc<AfxRule>],
+    pfx_rules: &[&'a Arc<AfxRule>],
+    sfx_rules: &[&'a Arc<AfxRule>],
+    ctx: &AfxContext<'a>,
     dict_meta: Option<&Meta>,
     dest: &mut WordList,
+    substandard_dest: &mut WordList,
 ) -> bool {
     if pfx_rules.is_empty() && sfx_rules.is_empty() {
         return false;
     }
 
-    // Store words with prefixes that can also have suffixes
-    let mut pfxd_maybe_sfx: Vec<PossibleCombination> = Vec::new();
-    let mut rule_found = false;
+    let (twofold_rules, single_rules, single_kind) = if ctx.complex_prefixes {
+        (pfx_rules, sfx_rules, RuleType::Suffix)
+    } else {
+        (sfx_rules, pfx_rules, RuleType::Prefix)
+    };
 
-    for &pfx_rule in pfx_rules {
-        // Locate matching prefix rules
-        for (pat_idx, prefixed) in pfx_rule.apply_patterns(stem) {
-            store_applied_pattern(stem, pfx_rule, pat_idx, &prefixed, dict_meta, dest);
+    let twofold_words = apply_twofold(stem, twofold_rules, ctx);
+    let mut rule_found = !twofold_words.is_empty();
+
+    for (affixed, applied) in &twofold_words {
+        let dest = ctx.select_dest(applied, dest, substandard_dest);
+        store_applied_patterns(stem, applied, affixed, ctx, dict_meta, dest);
+    }
 
+    for &rule in single_rules {
+        for (pat_idx, affixed) in rule.apply_patterns(stem) {
+            let applied = [(rule, pat_idx)];
+            let dest = ctx.select_dest(&applied, dest, substandard_dest);
+            store_applied_patterns(stem, &applied, &affixed, ctx, dict_meta, dest);
             rule_found = true;
+        }
+    }
+
+    // Combine the opposite affix type with words from twofold rules, if both can
+    // combine. Continuation flags may allow additional affixes.
+    for (affixed, applied) in &twofold_words {
+        if !applied[0].0.can_combine() {
+            continue;
+        }
 
-            // Save rules that can have a prefix and a suffix
-            if pfx_rule.can_combine() {
-                pfxd_maybe_sfx.push((prefixed, pfx_rule, pat_idx));
+        let mut combine_rules: Vec<&Arc<AfxRule>> = single_rules.to_vec();
+        for rule in ctx.cont_rules(applied, single_kind) {
+            if !combine_rules.iter().any(|r| Arc::ptr_eq(r, rule)) {
+                combine_rules.push(rule);
+            }
+        }
+
+        for rule in combine_rules.into_iter().filter(|r| r.can_combine()) {
+            for (pat_idx, new_word) in rule.apply_patterns(affixed) {
+                let mut combined = applied.clone();
+                if rule.is_pfx() {
+                    combined.insert(0, (rule, pat_idx));
+                } else {
+                    combined.push((rule, pat_idx));
+                }
+                let dest = ctx.select_dest(&combined, dest, substandard_dest);
+                store_applied_patterns(stem, &combined, &new_word, ctx, dict_meta, dest);
             }
         }
     }
 
-    for &sfx_rule in sfx_rules {
-        // Locate matching prefix rules
-        for (pat_idx, suffixed) in sfx_rule.apply_patterns(stem) {
-            store_applied_pattern(stem, sfx_rule, pat_idx, &suffixed, dict_meta, dest);
-            rule_found = true;
+    rule_found
+}
 
-            if sfx_rule.can_combine() {
-                apply_combo_words(stem, &pfxd_maybe_sfx, sfx_rule, dict_meta, dest);
+/// Apply rules that may have a second rule of the same kind from continuation flags.
+/// Returns the created words with the rules that were applied, innermost first.
+fn apply_twofold<'a>(
+    stem: &str,
+    rules: &[&'a Arc<AfxRule>],
+    ctx: &AfxContext<'a>,
+) -> Vec<(String, Vec<Applied<'a>>)> {
+    let mut ret = Vec::new();
+
+    for &rule in rules {
+        for (pat_idx, affixed) in rule.apply_patterns(stem) {
+            let applied = [(rule, pat_idx)];
+
+            for outer_rule in ctx.cont_rules(&applied, rule.kind()) {
+                for (outer_idx, outer_affixed) in outer_rule.apply_patterns(&affixed) {
+                    ret.push((
+                        outer_affixed,
+                        vec![(rule, pat_idx), (outer_rule, outer_idx)],
+                    ));
+                }
             }
+
+            ret.push((affixed, applied.to_vec()));
         }
     }
 
-    rule_found
+    ret
 }
 
-/// Create meta and store an applied pattern to a wordlist
-fn store_applied_pattern(
+/// Create meta and store a word created from applied patterns to a wordlist, if the
+/// combination of affixes is allowed
+fn store_applied_patterns(
     stem_arc: &Arc<str>,      // stem word
-    rule: &Arc<AfxRule>,      // rule that was applied
-    pat_idx: usize,           // index of the relevant pattern within the rule
+    applied: &[Applied],      // rules and pattern indices that were applied
     affixed: &str,            // affixed (created) word
+    ctx: &AfxContext,         // flag information
     dict_meta: Option<&Meta>, // metadata from the dictionary entry
     dest: &mut WordList,      // store the result here
 ) {
-    // Create metadata for this application
-    let meta = Meta::new(Arc::clone(stem_arc), Source::new_affix(rule, pat_idx));
+    if !ctx.form_allowed(applied) {
+        return;
+    }
 
     // Add this entry to the wordlist or update an existing one
     let meta_vec = dest.0.entry_ref(affixed).or_default();
-    meta_vec.push(meta);
+    push_applied_metas(stem_arc, applied, dict_meta, meta_vec);
+}
+
+/// Create metadata for each applied pattern, followed by metadata from the dictionary
+pub(super) fn push_applied_metas(
+    stem_arc: &Arc<str>,
+    applied: &[Applied],
+    dict_meta: Option<&Meta>,
+    dest: &mut Vec<Meta>,
+) {
+    for (rule, pat_idx) in applied {
+        let meta = Meta::new(Arc::clone(stem_arc), Source::new_affix(rule, *pat_idx));
+        dest.push(meta);
+    }
 
     if let Some(meta) = dict_meta {
-        meta_vec.push(Meta::clone(meta));
+        dest.push(Meta::clone(meta));
     }
 }
 
-/// Given a list of words that are eligible for combinations, check if a rule applies. If
-/// so, save it to the word list
-#[allow(clippy::similar_names)] // pfx sfx...
-fn apply_combo_words(
-    stem_arc: &Arc<str>,
-    pfxd_maybe_sfx: &[PossibleCombination],
-    rule: &Arc<AfxRule>,
-    dict_meta: Option<&Meta>, // metadata from the dictionary entry
-    dest: &mut WordList,
-) {
-    for (prefixed, pfx_rule, pfx_idx) in pfxd_maybe_sfx {
-        for (sfx_idx, new_word) in rule.apply_patterns(prefixed) {
-            let meta_vec = dest.0.entry_ref(new_word.as_str()).or_insert_with(Vec::new);
+/// Segment words by unicode boundaries.
+///
+/// Segments made up only of `word_chars` (from `WORDCHARS`) join the segments on either
+/// side of them, so e.g. `e.g.` is a single word if `.` is a word character. Joiners at
+/// the start or end of a word are not part of it, except for a single trailing `.`. Only
+/// words containing alphanumeric characters are returned.
+pub fn word_splitter<'a, 'w>(s: &'a str, word_chars: &'w str) -> WordSplitter<'a, 'w> {
+    WordSplitter {
+        input: s,
+        bounds: s.split_word_bound_indices().peekable(),
+        word_chars,
+    }
+}
+
+/// Iterator over `(index, word)`, created by [`word_splitter`]
+pub struct WordSplitter<'a, 'w> {
+    input: &'a str,
+    bounds: Peekable<UWordBoundIndices<'a>>,
+    word_chars: &'w str,
+}
+
+impl<'a> Iterator for WordSplitter<'a, '_> {
+    type Item = (usize, &'a str);
 
-            let meta_pfx = Meta::new(stem_arc.clone(), Source::new_affix(pfx_rule, *pfx_idx));
-            let meta_sfx = Meta::new(stem_arc.clone(), Source::new_affix(rule, sfx_idx));
-            meta_vec.push(meta_pfx);
-            meta_vec.push(meta_sfx);
+    fn next(&mut self) -> Option<Self::Item> {
+        let word_chars = self.word_chars;
+        let is_joiner =
+            |seg: &str| !word_chars.is_empty() && seg.chars().all(|c| word_chars.contains(c));
+        let is_word = |seg: &str| seg.chars().any(char::is_alphanumeric);
+
+        loop {
+            let (start, seg) = self.bounds.next()?;
+            let mut last_joiner = is_joiner(seg);
+            if !last_joiner && !is_word(seg) {
+                continue;
+            }
 
-            if let Some(meta) = dict_meta {
-                meta_vec.push(Meta::clone(meta));
+            // Byte range of the first to last word segment, and whether the joiner right
+            // after it starts with a period
+            let mut word: Option<(usize, usize)> = None;
+            let mut period = false;
+            let mut add = |idx: usize, seg: &str, joiner: bool| {
+                if !joiner {
+                    word = Some((word.map_or(idx, |w| w.0), idx + seg.len()));
+                    period = false;
+                } else if word.map_or(false, |w| w.1 == idx) {
+                    period = seg.starts_with('.');
+                }
+            };
+            add(start, seg, last_joiner);
+
+            // A joiner may follow anything, a word may only follow a joiner
+            while let Some((idx, next)) = self
+                .bounds
+                .next_if(|(_, next)| is_joiner(next) || (last_joiner && is_word(next)))
+            {
+                last_joiner = is_joiner(next);
+                add(idx, next, last_joiner);
+            }
+
+            if let Some((start, end)) = word {
+                let end = end + usize::from(period);
+                return Some((start, &self.input[start..end]));
             }
         }
     }
 }
 
-/// Segment words by unicode boundaries.
-pub fn word_splitter(s: &str) -> impl Iterator<Item = (usize, &str)> {
-    s.split_word_bound_indices()
-        .filter(|split| split.1.chars().all(|c| c.is_alphanumeric() || c == '-'))
-}
-
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
-    use crate::affix::RuleType;
 
     #[test]
     fn test_create_words() {
@@ -182,10 +362,27 @@ mod tests {
             ),
         ];
 
+        let flags = BTreeMap::new();
+        let ctx = AfxContext {
+            affix_flags: &flags,
+            rule_flags: &flags,
+            complex_prefixes: false,
+        };
+
         for (i, (word, pfxs, sfxs, expected_slice)) in conditions.iter().enumerate() {
             let mut dest = WordList::new();
+            let mut substandard_dest = WordList::new();
             let stem_rc = Arc::from(*word);
-            create_affixed_word_map(&stem_rc, pfxs, sfxs, None, &mut dest);
+            create_affixed_word_map(
+                &stem_rc,
+                pfxs,
+                sfxs,
+                &ctx,
+                None,
+                &mut dest,
+                &mut substandard_dest,
+            );
+            assert!(substandard_dest.0.is_empty());
 
             let tmp: Vec<(Box<str>, _)> = dest.0.into_iter().collect();
             let mut result: Vec<_> = tmp.iter().map(|(s, _)| s.as_ref()).collect();
@@ -203,9 +400,68 @@ mod tests {
     #[test]
     fn test_word_splitter() {
         let s = "the quick brown.     Fox Jum-ped --\t where? 'over' (the) very--lazy dog";
-        let _: Vec<_> = dbg!(word_splitter(s).collect());
-        let _: Vec<_> = dbg!(s.split_word_bound_indices().collect());
-        // FIXME: do something with these
+        let words: Vec<_> = word_splitter(s, "").map(|(_, w)| w).collect();
+        let expected = [
+            "the", "quick", "brown", "Fox", "Jum", "ped", "where", "over", "the", "very", "lazy",
+            "dog",
+        ];
+        assert_eq!(words, expected);
+
+        // A lone `--` is not a word
+        let words: Vec<_> = word_splitter(s, "-").collect();
+        let expected = [
+            (0, "the"),
+            (4, "quick"),
+            (10, "brown"),
+            (21, "Fox"),
+            (25, "Jum-ped"),
+            (37, "where"),
+            (45, "over"),
+            (52, "the"),
+            (57, "very--lazy"),
+            (68, "dog"),
+        ];
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn test_word_splitter_word_chars() {
+        let s = "don't use e.g. 3D or 1.5 ideas, ok.";
+        let words: Vec<_> = word_splitter(s, "").map(|(_, w)| w).collect();
+        assert_eq!(
+            words,
+            ["don't", "use", "e.g", "3D", "or", "1.5", "ideas", "ok"]
+        );
+
+        let words: Vec<_> = word_splitter(s, ".").map(|(_, w)| w).collect();
+        assert_eq!(
+            words,
+            ["don't", "use", "e.g.", "3D", "or", "1.5", "ideas", "ok."]
+        );
+
+        // Typographic apostrophes only join words if they are word characters
+        let s = "rock’n’roll";
+        let words: Vec<_> = word_splitter(s, "’").map(|(_, w)| w).collect();
+        assert_eq!(words, ["rock’n’roll"]);
+    }
+
+    #[test]
+    fn test_word_splitter_trim() {
+        // Word characters only count between words
+        let s = "'over' said over- and over... -(well-known)- e.g.!";
+        let words: Vec<_> = word_splitter(s, ".-'").collect();
+        assert_eq!(
+            words,
+            [
+                (1, "over"),
+                (7, "said"),
+                (12, "over"),
+                (18, "and"),
+                (22, "over."),
+                (32, "well-known"),
+                (45, "e.g."),
+            ]
+        );
     }
 }
 
@@ -1,21 +1,218 @@
-//! Take rules and qpply them to a word, trying to find a match in an
-//! existing wordlist.
-#![allow(unused)]
+//! Take rules and apply them in reverse to a word, stripping affixes to find a
+//! stem in an existing wordlist. This is used instead of expanding every affixed
+//! form when building with [`Strategy::Lazy`](super::Strategy::Lazy).
 
-use crate::affix::CompoundConfig;
-use crate::Dictionary;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-/// Try to create a word
-fn entrypoint(dict: &Dictionary, word: &str) -> bool {
-    todo!()
+use hashbrown::HashMap;
+
+use super::meta::Meta;
+use super::rule::AfxRule;
+use super::rules_apply::{push_applied_metas, AfxContext, Applied};
+use super::{Flag, FlagValue};
+
+// FIXME: compound words (`COMPOUNDRULE`, `COMPOUNDFLAG`, etc) are not yet checked
+
+/// Properties of a stem that also apply to all words created from it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct StemAttrs {
+    /// From `FORBIDDENWORD`
+    pub forbid: bool,
+    /// From `NOSUGGEST`
+    pub nosuggest: bool,
+    /// From `WARN`
+    pub warn: bool,
+    /// From `SUBSTANDARD`
+    pub substandard: bool,
+}
+
+/// A dictionary stem that has affix rules
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct LazyStem {
+    /// Prefix and suffix rules referenced by the stem's flags
+    pub rules: Vec<Arc<AfxRule>>,
+    /// Metadata from the dictionary entry
+    pub dict_meta: Option<Meta>,
+    pub attrs: StemAttrs,
+}
+
+/// Stems and rules needed to check affixed words without expanding them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct LazyIndex {
+    prefixes: Vec<Arc<AfxRule>>,
+    suffixes: Vec<Arc<AfxRule>>,
+    stems: HashMap<Arc<str>, Vec<LazyStem>>,
 }
 
-fn try_strip_pfx() {
-    todo!()
+/// A word that was found by stripping affixes
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Derived {
+    /// Metadata for the applied affixes and the stem
+    pub metas: Vec<Meta>,
+    /// Attributes of the stem, plus `substandard` if any affix is
+    pub attrs: StemAttrs,
 }
 
-fn try_strip_sfx() {}
+impl LazyIndex {
+    /// Collect all affix rules from a dictionary's flags
+    pub fn new(affix_flags: &BTreeMap<Flag, FlagValue>) -> Self {
+        let mut ret = Self::default();
+
+        for value in affix_flags.values() {
+            if let FlagValue::Rule(rule) = value {
+                if rule.is_pfx() {
+                    ret.prefixes.push(Arc::clone(rule));
+                } else {
+                    ret.suffixes.push(Arc::clone(rule));
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Add a stem that may be used with affixes
+    pub fn insert(&mut self, stem: &Arc<str>, entry: LazyStem) {
+        self.stems.entry(Arc::clone(stem)).or_default().push(entry);
+    }
+
+    /// Remove a stem and its rules, returns `true` if it existed
+    pub fn remove(&mut self, stem: &str) -> bool {
+        self.stems.remove(stem).is_some()
+    }
+
+    /// All stems that have affix rules
+    pub fn stems(&self) -> impl Iterator<Item = (&Arc<str>, &[LazyStem])> {
+        self.stems
+            .iter()
+            .map(|(stem, entries)| (stem, entries.as_slice()))
+    }
+
+    /// Entries for a single stem
+    pub fn get(&self, stem: &str) -> &[LazyStem] {
+        self.stems.get(stem).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.stems.shrink_to_fit();
+    }
+
+    /// Find all ways that `word` can be created from a stem and its affixes. Does not
+    /// include the stem on its own.
+    ///
+    /// This mirrors [`create_affixed_word_map`](super::rules_apply::create_affixed_word_map):
+    /// one affix type may be twofold and the other may only be applied once, outermost.
+    pub fn derive(&self, word: &str, ctx: &AfxContext) -> Vec<Derived> {
+        let (twofold_rules, single_rules) = if ctx.complex_prefixes {
+            (&self.prefixes, &self.suffixes)
+        } else {
+            (&self.suffixes, &self.prefixes)
+        };
 
-/// Try splitting the word at each position and testing the parts according to
-/// compound rules
-fn compound_thing(cfg: &CompoundConfig) {}
+        let mut ret = Vec::new();
+        self.derive_twofold(word, None, twofold_rules, ctx, &mut ret);
+
+        for rule in single_rules {
+            for (pat_idx, base) in rule.strip_patterns(word) {
+                self.derive_twofold(&base, Some((rule, pat_idx)), twofold_rules, ctx, &mut ret);
+            }
+        }
+
+        ret
+    }
+
+    /// Strip up to two affixes of the twofold type, then look for a matching stem
+    fn derive_twofold(
+        &self,
+        word: &str,
+        single: Option<Applied>,
+        twofold_rules: &[Arc<AfxRule>],
+        ctx: &AfxContext,
+        dest: &mut Vec<Derived>,
+    ) {
+        if single.is_some() {
+            self.match_stems(word, &[], single, ctx, dest);
+        }
+
+        for outer in twofold_rules {
+            for (outer_idx, base) in outer.strip_patterns(word) {
+                let outer_applied = (outer, outer_idx);
+                self.match_stems(&base, &[outer_applied], single, ctx, dest);
+
+                for inner in twofold_rules {
+                    for (inner_idx, stem) in inner.strip_patterns(&base) {
+                        let inner_applied = (inner, inner_idx);
+
+                        // The outer rule must come from the inner rule's continuation flags
+                        if ctx
+                            .cont_rules(&[inner_applied], outer.kind())
+                            .any(|rule| Arc::ptr_eq(rule, outer))
+                        {
+                            let applied = [inner_applied, outer_applied];
+                            self.match_stems(&stem, &applied, single, ctx, dest);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check whether a stem exists that allows the given affixes, and store the result
+    fn match_stems(
+        &self,
+        stem: &str,
+        twofold: &[Applied],
+        single: Option<Applied>,
+        ctx: &AfxContext,
+        dest: &mut Vec<Derived>,
+    ) {
+        let Some((stem_arc, entries)) = self.stems.get_key_value(stem) else {
+            return;
+        };
+
+        let mut applied = twofold.to_vec();
+        if let Some(app) = single {
+            if app.0.is_pfx() {
+                applied.insert(0, app);
+            } else {
+                applied.push(app);
+            }
+        }
+
+        if !ctx.form_allowed(&applied) {
+            return;
+        }
+
+        for entry in entries {
+            let has_rule = |rule: &Arc<AfxRule>| entry.rules.iter().any(|r| Arc::ptr_eq(r, rule));
+
+            let allowed = match (twofold.first(), single) {
+                (None, None) => false,
+                (Some((inner, _)), None) => has_rule(inner),
+                (None, Some((rule, _))) => has_rule(rule),
+                // Combining affix types: the single rule may also come from continuation flags
+                (Some((inner, _)), Some((rule, _))) => {
+                    has_rule(inner)
+                        && inner.can_combine()
+                        && rule.can_combine()
+                        && (has_rule(rule)
+                            || ctx
+                                .cont_rules(twofold, rule.kind())
+                                .any(|r| Arc::ptr_eq(r, rule)))
+                }
+            };
+
+            if !allowed {
+                continue;
+            }
+
+            let mut metas = Vec::new();
+            push_applied_metas(stem_arc, &applied, entry.dict_meta.as_ref(), &mut metas);
+
+            let mut attrs = entry.attrs;
+            attrs.substandard |= ctx.is_substandard(&applied);
+            dest.push(Derived { metas, attrs });
+        }
+    }
+}
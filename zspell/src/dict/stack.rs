@@ -0,0 +1,275 @@
+//! Check words against several dictionaries at once
+
+use std::borrow::Cow;
+use std::fmt;
+
+use super::rules_apply::word_splitter;
+use super::{Dictionary, WordEntry};
+use crate::morph::MorphInfo;
+use crate::ParsedCfg;
+
+/// Multiple dictionaries that are checked as one, such as a base language dictionary
+/// combined with domain-specific dictionaries or lists of product names.
+///
+/// Layers that are pushed later take priority. A word is decided by the highest layer
+/// that either accepts it or forbids it: forbidding a word in a domain dictionary
+/// rejects it even if the base dictionary accepts it, and vice versa. Stems, analysis
+/// and suggestions are combined from all layers.
+///
+/// ```
+/// use zspell::{DictBuilder, DictionaryStack};
+///
+/// let base = DictBuilder::new()
+///     .config_str("")
+///     .dict_str("apple\nbanana\nirregardless")
+///     .build()
+///     .unwrap();
+///
+/// let domain = DictBuilder::new()
+///     .config_str("")
+///     .dict_str("zspell")
+///     .personal_str("*irregardless")
+///     .build()
+///     .unwrap();
+///
+/// let mut stack = DictionaryStack::new();
+/// stack.push(base);
+/// stack.push(domain);
+/// stack.push_word_list("Hunspell\nNuspell");
+///
+/// assert!(stack.check("apple zspell Hunspell"));
+/// assert!(!stack.check_word("irregardless"));
+/// assert!(stack.entry("irregardless").forbidden());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DictionaryStack {
+    /// Lowest priority first
+    layers: Vec<Dictionary>,
+    /// Word characters of all layers
+    word_chars: String,
+}
+
+impl DictionaryStack {
+    /// Create an empty stack. No words are accepted until layers are added.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a dictionary that takes priority over all existing layers
+    #[inline]
+    pub fn push(&mut self, dict: Dictionary) {
+        for ch in dict.parsed_config.word_chars().chars() {
+            if !self.word_chars.contains(ch) {
+                self.word_chars.push(ch);
+            }
+        }
+        self.layers.push(dict);
+    }
+
+    /// Add a plain list of words, one per line, as a layer that takes priority over all
+    /// existing layers. Words are accepted exactly as written, without affixes.
+    #[inline]
+    pub fn push_word_list(&mut self, words: &str) {
+        let mut dict = Dictionary::new(ParsedCfg::default())
+            .expect("the default configuration is always valid");
+        dict.add_word_list(words);
+        self.push(dict);
+    }
+
+    /// All layers, lowest priority first
+    #[inline]
+    pub fn layers(&self) -> &[Dictionary] {
+        &self.layers
+    }
+
+    /// Mutable access to the layers, e.g. to add words at runtime. Lowest priority first.
+    #[inline]
+    pub fn layers_mut(&mut self) -> &mut [Dictionary] {
+        &mut self.layers
+    }
+
+    /// Check that an entire string contains only words that are spelled correctly,
+    /// returns `true` if so. See [`Dictionary::check`].
+    #[inline]
+    pub fn check(&self, input: &str) -> bool {
+        word_splitter(input, &self.word_chars).all(|(_, w)| self.check_word(w))
+    }
+
+    /// Check that a single word is spelled correctly, returns `true` if so. See
+    /// [`Dictionary::check_word`].
+    #[inline]
+    pub fn check_word(&self, word: &str) -> bool {
+        for dict in self.layers.iter().rev() {
+            let entry = dict.entry(word);
+            if entry.correct() {
+                return true;
+            }
+            // Words rejected by `FORBIDWARN` are not left to lower layers either
+            if entry.forbidden() || entry.warn() {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Check words in a string, returning a list of the start and end indices of any
+    /// incorrect words. See [`Dictionary::check_indices`].
+    #[inline]
+    pub fn check_indices<'a: 'd, 'd>(
+        &'d self,
+        input: &'a str,
+    ) -> impl Iterator<Item = (usize, &'a str)> + 'd {
+        word_splitter(input, &self.word_chars).filter(|(_idx, w)| !self.check_word(w))
+    }
+
+    /// Return an iterator over entries for each word in a sentence. See
+    /// [`Dictionary::entries`].
+    #[inline]
+    pub fn entries<'d, 's>(&'d self, input: &'s str) -> impl Iterator<Item = StackEntry<'d, 's>> {
+        word_splitter(input, &self.word_chars).map(|(idx, word)| self.entry_inner(word, idx))
+    }
+
+    /// Return an entry for a single word. See [`Dictionary::entry`].
+    #[inline]
+    pub fn entry<'d, 's>(&'d self, word: &'s str) -> StackEntry<'d, 's> {
+        self.entry_inner(word, 0)
+    }
+
+    fn entry_inner<'d, 's>(&'d self, word: &'s str, index: usize) -> StackEntry<'d, 's> {
+        let entries: Vec<WordEntry<'d, 's>> = self
+            .layers
+            .iter()
+            .rev()
+            .map(|dict| dict.entry(word))
+            .collect();
+        let decided = entries
+            .iter()
+            .find(|e| e.correct() || e.forbidden() || e.warn());
+
+        StackEntry {
+            stack: self,
+            word,
+            index,
+            correct: decided.map_or(false, WordEntry::correct),
+            forbidden: decided.map_or(false, WordEntry::forbidden),
+            entries,
+        }
+    }
+}
+
+/// The result of checking a word against a [`DictionaryStack`], combining the entries
+/// of each layer.
+///
+/// This type is created by [`DictionaryStack::entry`] and [`DictionaryStack::entries`].
+#[derive(Clone)]
+pub struct StackEntry<'dict, 'word> {
+    stack: &'dict DictionaryStack,
+    word: &'word str,
+    index: usize,
+    correct: bool,
+    forbidden: bool,
+    /// One entry for each layer, highest priority first
+    entries: Vec<WordEntry<'dict, 'word>>,
+}
+
+impl fmt::Debug for StackEntry<'_, '_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StackEntry")
+            .field("word", &self.word)
+            .field("index", &self.index)
+            .field("correct", &self.correct)
+            .field("forbidden", &self.forbidden)
+            .field("entries", &self.entries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'dict> StackEntry<'dict, '_> {
+    /// True if the word is accepted by the highest layer that knows it
+    #[inline]
+    pub fn correct(&self) -> bool {
+        self.correct
+    }
+
+    /// True if the word is forbidden by the highest layer that knows it
+    #[inline]
+    pub fn forbidden(&self) -> bool {
+        self.forbidden
+    }
+
+    /// The input word that was checked
+    #[inline]
+    pub fn word(&self) -> &str {
+        self.word
+    }
+
+    /// The byte index of the word in the input string, if created from
+    /// [`DictionaryStack::entries`]
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Entries from layers that accept the word and are not overridden by a layer that
+    /// forbids it
+    fn accepting(&self) -> impl Iterator<Item = &WordEntry<'dict, '_>> {
+        self.entries
+            .iter()
+            .take_while(|e| !e.forbidden())
+            .filter(|e| e.correct())
+    }
+
+    /// Stems of the word from every layer that accepts it, without duplicates. Returns
+    /// `None` if the word is not correct. See [`WordEntry::stems`].
+    #[inline]
+    pub fn stems(&self) -> Option<Vec<Cow<'dict, str>>> {
+        if !self.correct {
+            return None;
+        }
+
+        let mut ret: Vec<Cow<'dict, str>> = Vec::new();
+        for stem in self.accepting().filter_map(WordEntry::stems).flatten() {
+            if !ret.contains(&stem) {
+                ret.push(stem);
+            }
+        }
+        Some(ret)
+    }
+
+    /// Morphological analysis from every layer that accepts the word. Returns `None` if
+    /// the word is not correct. See [`WordEntry::analyze`].
+    #[inline]
+    pub fn analyze(&self) -> Option<Vec<&MorphInfo>> {
+        if !self.correct {
+            return None;
+        }
+
+        let ret = self
+            .accepting()
+            .filter_map(WordEntry::analyze)
+            .flatten()
+            .collect();
+        Some(ret)
+    }
+
+    /// Suggestions from every layer, excluding words that the stack does not accept.
+    /// Returns `None` if the word is correct. Feature gated behind
+    /// `unstable-suggestions`, see [`WordEntry::suggest`].
+    #[inline]
+    #[cfg(feature = "unstable-suggestions")]
+    pub fn suggest(&self) -> Option<Vec<Cow<'dict, str>>> {
+        if self.correct {
+            return None;
+        }
+
+        let mut ret: Vec<Cow<'dict, str>> = Vec::new();
+        for sugg in self.entries.iter().filter_map(WordEntry::suggest).flatten() {
+            if !ret.contains(&sugg) && self.stack.check_word(&sugg) {
+                ret.push(sugg);
+            }
+        }
+        Some(ret)
+    }
+}
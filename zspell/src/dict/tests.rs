@@ -22,7 +22,7 @@ fn test_update_personal() {
     ";
 
     let mut d = Dictionary::new(ParsedCfg::default()).unwrap();
-    d.parse_update_personal(personal_str, &[]).unwrap();
+    d.parse_update_personal(personal_str).unwrap();
     assert!(d.wordlist.0.contains_key("abcd"));
     assert!(d.wordlist.0.contains_key("efgh"));
     assert!(!d.wordlist.0.contains_key("ijkl"));
@@ -36,6 +36,45 @@ fn test_update_personal() {
     assert_eq!(stems, vec!["efgh", "something"]);
 }
 
+#[test]
+fn test_personal_friend() {
+    let affix_str = indoc! {"
+        SFX S Y 1
+        SFX S 0 's .
+    "};
+    let dict_str = indoc! {"
+        Kant/S
+        Kant po:noun
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let mut dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str(dict_str)
+            .personal_str("Kafka/Kant\nHegel/Nobody\nKleist/Kafka")
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        assert!(dict.check("Kafka Kafka's Hegel Kleist Kleist's"));
+        assert!(!dict.check_word("Hegel's"));
+
+        // The friend is stored with the personal word's metadata
+        let metas = &dict.wordlist.0["Kafka"];
+        let Source::Personal(personal) = metas[0].source() else {
+            panic!("{metas:?}");
+        };
+        assert_eq!(personal.friend().map(AsRef::as_ref), Some("Kant"));
+        let stems: Vec<_> = dict.entry("Kafka's").stems().unwrap().collect();
+        assert_eq!(stems, ["Kafka"]);
+
+        assert!(!dict.add_word_with_affix("Fichte", "Nobody"));
+        assert!(dict.check_word("Fichte"));
+        assert!(dict.add_word_with_affix("Schelling", "Kant"));
+        assert!(dict.check_word("Schelling's"));
+    }
+}
+
 #[test]
 #[cfg(not(miri))] // slow!
 fn test_builder() {
@@ -118,3 +157,1171 @@ fn test_morph() {
     assert_eq!(stems, ["drink"]);
     // assert_eq!(stems, ["drinkable", "drink"]);
 }
+
+#[test]
+fn test_forbid_warn() {
+    let aff_str = indoc! {"
+        FORBIDWARN
+        WARN W
+    "};
+
+    let d = DictBuilder::new()
+        .dict_str("with\nwhit/W")
+        .config_str(aff_str)
+        .build()
+        .unwrap();
+
+    // `warn` is the reason the word is rejected, it is not a forbidden word
+    let entry = d.entry("whit");
+    assert!(entry.warn());
+    assert!(!entry.forbidden());
+    assert!(!entry.correct());
+    assert!(!d.check_word("whit"));
+
+    let entry = d.entry("with");
+    assert!(!entry.warn());
+    assert!(entry.correct());
+}
+
+#[test]
+fn test_warn_shared_form() {
+    let aff_str = indoc! {"
+        FORBIDWARN
+        WARN W
+
+        SFX S Y 1
+        SFX S 0 s .
+    "};
+
+    // "bars" is created by both a `WARN` stem and a normal one, in either order
+    for dict_str in ["bar/WS\nbars", "bars\nbar/WS"] {
+        for strategy in [Strategy::Eager, Strategy::Lazy] {
+            let d = DictBuilder::new()
+                .dict_str(dict_str)
+                .config_str(aff_str)
+                .expansion(strategy)
+                .build()
+                .unwrap();
+
+            assert!(d.entry("bar").warn(), "{dict_str:?} {strategy:?}");
+            assert!(!d.check_word("bar"), "{dict_str:?} {strategy:?}");
+            assert!(!d.entry("bars").warn(), "{dict_str:?} {strategy:?}");
+            assert!(d.check_word("bars"), "{dict_str:?} {strategy:?}");
+        }
+    }
+}
+
+#[test]
+fn test_remove_shared_form() {
+    let aff_str = indoc! {"
+        WARN W
+        SUBSTANDARD ~
+
+        SFX S Y 1
+        SFX S 0 s .
+
+        SFX Z Y 1
+        SFX Z 0 s/~ .
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let mut d = DictBuilder::new()
+            .dict_str("bar/WS\nbars\ncat/Z\ncats")
+            .config_str(aff_str)
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        assert!(!d.entry("bars").warn(), "{strategy:?}");
+        assert!(!d.entry("cats").substandard(), "{strategy:?}");
+
+        // Only the flagged stems still create these forms
+        d.remove_word("bars");
+        d.remove_word("cats");
+        for word in ["bars", "cats"] {
+            assert!(d.check_word(word), "{word} {strategy:?}");
+        }
+        assert!(d.entry("bars").warn(), "{strategy:?}");
+        assert!(d.entry("cats").substandard(), "{strategy:?}");
+        assert!(!d.entry("cat").substandard(), "{strategy:?}");
+    }
+}
+
+#[test]
+fn test_substandard() {
+    let aff_str = indoc! {"
+        SUBSTANDARD S
+
+        SFX A Y 2
+        SFX A 0 ed .
+        SFX A 0 t/S .
+
+        SFX B Y 1
+        SFX B 0 t .
+    "};
+
+    let d = DictBuilder::new()
+        .dict_str("burn/A\nthier/S\nlearn/AB")
+        .config_str(aff_str)
+        .build()
+        .unwrap();
+
+    for word in ["burnt", "thier"] {
+        let entry = d.entry(word);
+        assert!(entry.substandard(), "{word}");
+        assert!(entry.correct(), "{word}");
+    }
+
+    // "learnt" also comes from a standard affix
+    for word in ["burn", "burned", "learnt"] {
+        let entry = d.entry(word);
+        assert!(!entry.substandard(), "{word}");
+        assert!(entry.correct(), "{word}");
+    }
+}
+
+#[test]
+fn test_substandard_order() {
+    let aff_str = indoc! {"
+        SUBSTANDARD ~
+
+        SFX S Y 1
+        SFX S 0 s .
+
+        SFX Z Y 1
+        SFX Z 0 s/~ .
+    "};
+
+    // A word is only substandard if every way of creating it is
+    for dict_str in ["bar/Z\nbar/S", "bar/S\nbar/Z"] {
+        for strategy in [Strategy::Eager, Strategy::Lazy] {
+            let d = DictBuilder::new()
+                .dict_str(dict_str)
+                .config_str(aff_str)
+                .expansion(strategy)
+                .build()
+                .unwrap();
+
+            let entry = d.entry("bars");
+            assert!(entry.correct(), "{dict_str:?} {strategy:?}");
+            assert!(!entry.substandard(), "{dict_str:?} {strategy:?}");
+        }
+    }
+}
+
+#[test]
+fn test_builder_bytes() {
+    let d = DictBuilder::new()
+        .config_bytes(b"SET KOI8-R\nTRY \xcd\xc9\xd2\n")
+        .dict_bytes(b"1\n\xcd\xc9\xd2\n")
+        .build()
+        .unwrap();
+    assert!(d.check("мир"));
+
+    // Without `SET`, bytes are UTF-8
+    let d = DictBuilder::new()
+        .config_bytes(b"")
+        .dict_bytes("1\nмир\n".as_bytes())
+        .build()
+        .unwrap();
+    assert!(d.check("мир"));
+
+    let err = DictBuilder::new()
+        .config_bytes(b"SET ISCII-DEVANAGARI\n")
+        .dict_bytes(b"1\nabc\n")
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::Build(BuildError::UnsupportedEncoding(Encoding::IsciiDevanagari))
+    );
+
+    let err = DictBuilder::new()
+        .config_str("SET UTF-8")
+        .dict_bytes(b"1\ncaf\xe9\n")
+        .build()
+        .unwrap_err();
+    assert_eq!(err, Error::Build(BuildError::Decode(Encoding::Utf8)));
+}
+
+#[test]
+fn test_lazy_strategy() {
+    let aff_str = indoc! {"
+        FORBIDDENWORD !
+
+        PFX P Y 1
+        PFX P 0 re .
+
+        SFX S Y 2
+        SFX S y ies [^aeiou]y
+        SFX S 0 s [aeiou]y
+    "};
+
+    let d = DictBuilder::new()
+        .dict_str("fly/PS\nday/S\nbay/!S\nZoey/S")
+        .config_str(aff_str)
+        .expansion(Strategy::Lazy)
+        .build()
+        .unwrap();
+
+    // Affixed forms are not stored
+    assert!(d.wordlist.0.contains_key("fly"));
+    assert!(!d.wordlist.0.contains_key("flies"));
+
+    assert!(d.check("fly flies refly reflies Flies days"));
+    for word in ["flys", "dayies", "reday", "bays", "zoeys"] {
+        assert!(!d.check_word(word), "{word}");
+    }
+    assert!(d.check_word("Zoeys"));
+    assert!(d.entry("bays").forbidden());
+
+    let entry = d.entry("reflies");
+    let stems: Vec<_> = entry.stems().unwrap().collect();
+    assert_eq!(stems, ["fly"]);
+    assert_eq!(entry.matched_entry(), Some("reflies"));
+}
+
+#[test]
+#[cfg(not(miri))] // slow!
+fn test_lazy_matches_eager() {
+    let aff_content = fs::read_to_string("tests/files/w1_eng_short.aff").unwrap();
+    let dic_content = fs::read_to_string("tests/files/w1_eng_short.dic").unwrap();
+    let builder = DictBuilder::new()
+        .config_str(&aff_content)
+        .dict_str(&dic_content);
+    let eager = builder.clone().build().unwrap();
+    let lazy = builder.expansion(Strategy::Lazy).build().unwrap();
+
+    assert!(lazy.wordlist.0.len() < eager.wordlist.0.len());
+
+    for word in eager.wordlist.0.keys() {
+        assert!(lazy.check_word(word), "{word}");
+        let mut stems_eager: Vec<_> = eager.entry(word).stems().unwrap().collect();
+        let mut stems_lazy: Vec<_> = lazy.entry(word).stems().unwrap().collect();
+        stems_eager.sort_unstable();
+        stems_lazy.sort_unstable();
+        assert_eq!(stems_eager, stems_lazy, "{word}");
+    }
+
+    assert!(!lazy.check("pine missssspelled"));
+}
+
+#[test]
+#[cfg(not(miri))] // slow!
+fn test_cache() {
+    let aff_content = fs::read_to_string("tests/files/w1_eng_short.aff").unwrap();
+    let dic_content = fs::read_to_string("tests/files/w1_eng_short.dic").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let cache_path = dir.path().join("en.cache");
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let builder = DictBuilder::new()
+            .config_str(&aff_content)
+            .dict_str(&dic_content)
+            .personal_str("zspell\n*pine")
+            .expansion(strategy)
+            .cache_path(&cache_path);
+
+        let built = builder.clone().build().unwrap();
+        let cache_bytes = fs::read(&cache_path).unwrap();
+        assert!(cache::read(&cache_bytes, builder.checksum()).is_some());
+        assert!(cache::read(&cache_bytes, 0).is_none());
+
+        // A corrupted count is rejected rather than allocated
+        let mut corrupt = cache_bytes.clone();
+        let at = 12 + env!("CARGO_PKG_VERSION").len() + 8 + 4 + aff_content.len();
+        corrupt[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(cache::read(&corrupt, builder.checksum()).is_none());
+        let loaded = builder.clone().build().unwrap();
+
+        assert_eq!(loaded.wordlist, built.wordlist);
+        assert_eq!(loaded.wordlist_nosuggest, built.wordlist_nosuggest);
+        assert_eq!(loaded.wordlist_forbidden, built.wordlist_forbidden);
+        assert_eq!(loaded.stems, built.stems);
+        assert_eq!(loaded.stem_flags, built.stem_flags);
+        assert_eq!(loaded.alt_stems, built.alt_stems);
+        assert_eq!(loaded.lazy, built.lazy);
+        assert_eq!(loaded.parsed_config, built.parsed_config);
+        assert!(loaded.check("reptiles pillow bananas zspell"));
+        assert!(!loaded.check("pine"));
+        // Loading does not rewrite the cache
+        assert_eq!(fs::read(&cache_path).unwrap(), cache_bytes);
+
+        // A changed source rebuilds the cache
+        let changed = builder.personal_str("zspell").build().unwrap();
+        assert!(changed.check("pine"));
+        assert_ne!(fs::read(&cache_path).unwrap(), cache_bytes);
+    }
+
+    // An unreadable cache is replaced
+    fs::write(&cache_path, b"ZSPC garbage").unwrap();
+    let d = DictBuilder::new()
+        .config_str(&aff_content)
+        .dict_str(&dic_content)
+        .cache_path(&cache_path)
+        .build()
+        .unwrap();
+    assert!(d.check("reptiles"));
+    assert!(fs::read(&cache_path).unwrap().len() > 100);
+    // No temporary files are left behind
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    // Failing to write the cache does not fail the build
+    let missing_dir = dir.path().join("missing").join("en.cache");
+    let d = DictBuilder::new()
+        .config_str(&aff_content)
+        .dict_str(&dic_content)
+        .cache_path(&missing_dir)
+        .build()
+        .unwrap();
+    assert!(d.check("reptiles"));
+    assert!(!missing_dir.exists());
+}
+
+#[test]
+#[cfg(not(miri))] // slow!
+fn test_compact_matches_dict() {
+    let aff_content = fs::read_to_string("tests/files/w1_eng_short.aff").unwrap();
+    let dic_content = fs::read_to_string("tests/files/w1_eng_short.dic").unwrap();
+    let builder = DictBuilder::new()
+        .config_str(&aff_content)
+        .dict_str(&dic_content)
+        .personal_str("zspell\n*pine");
+    let dict = builder.clone().build().unwrap();
+    let compact = CompactDictionary::new(builder.build_compact().unwrap()).unwrap();
+
+    let all_words: HashSet<_> = dict
+        .wordlist
+        .0
+        .keys()
+        .chain(dict.wordlist_forbidden.0.keys())
+        .collect();
+    assert_eq!(compact.len(), all_words.len());
+
+    for word in dict.wordlist.0.keys() {
+        if dict.wordlist_forbidden.0.contains_key(word) {
+            continue;
+        }
+        let mut stems_dict: Vec<_> = dict.entry(word).stems().unwrap().collect();
+        let mut stems_compact = compact.stems(word).unwrap();
+        stems_dict.sort_unstable();
+        stems_compact.sort_unstable();
+        assert_eq!(stems_dict, stems_compact, "{word}");
+    }
+
+    assert!(compact.check("Reptiles zspell bananas."));
+    assert!(!compact.check_word("pine"));
+    assert!(compact.stems("pine").is_none());
+    let errors: Vec<_> = compact.check_indices("pine missspelled").collect();
+    assert_eq!(errors, [(0, "pine"), (5, "missspelled")]);
+}
+
+#[test]
+fn test_forbidden_capitalized() {
+    let affix_str = indoc! {"
+        SFX S Y 1
+        SFX S 0 s .
+    "};
+    let builder = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str("apple/S\nbanana/S")
+        .personal_str("*apples");
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let mut dict = builder.clone().expansion(strategy).build().unwrap();
+        dict.forbid_word("banana");
+
+        // Capitalized forms of forbidden words are rejected everywhere
+        for word in ["apples", "Apples", "banana", "Banana", "BANANA"] {
+            assert!(!dict.check_word(word), "{word} {strategy:?}");
+            assert!(!dict.check(word), "{word} {strategy:?}");
+            assert!(dict.entry(word).forbidden(), "{word} {strategy:?}");
+        }
+        assert!(dict.check("Apple Bananas"));
+
+        let mut stack = DictionaryStack::new();
+        stack.push(dict);
+        assert!(!stack.check("Apples Banana"));
+    }
+
+    let bytes = builder.build_compact().unwrap();
+    let compact = CompactDictionary::new(bytes.as_slice()).unwrap();
+    assert!(!compact.check_word("Apples"));
+    assert!(compact.check("Apple"));
+}
+
+#[test]
+fn test_compact() {
+    let affix_str = indoc! {"
+        WARN W
+        FORBIDWARN
+        OCONV 1
+        OCONV a A
+
+        SFX S Y 1
+        SFX S 0 s .
+    "};
+    let dict_str = indoc! {"
+        cat/S
+        NASA
+        foo/W
+    "};
+
+    let bytes = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str(dict_str)
+        .build_compact()
+        .unwrap();
+    let dict = CompactDictionary::new(bytes.as_slice()).unwrap();
+
+    assert!(dict.check("cat cats Cats NASA"));
+    assert!(!dict.check_word("nasa"));
+    assert!(!dict.check_word("foo"));
+    assert_eq!(dict.stems("cats").unwrap(), ["cAt"]);
+
+    // Invalid or truncated data is an error, not a panic
+    let err = Error::Build(BuildError::InvalidCompact);
+    assert_eq!(
+        CompactDictionary::new(&bytes[..bytes.len() - 1]).unwrap_err(),
+        err
+    );
+    assert_eq!(CompactDictionary::new(&b"ZSPC"[..]).unwrap_err(), err);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+#[cfg(not(miri))] // slow!
+fn test_parallel_matches_serial() {
+    let aff_content = fs::read_to_string("tests/files/w1_eng_short.aff").unwrap();
+    let dic_content = fs::read_to_string("tests/files/w1_eng_short.dic").unwrap();
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let builder = DictBuilder::new()
+            .config_str(&aff_content)
+            .dict_str(&dic_content)
+            .expansion(strategy);
+        let serial = builder.clone().parallel(false).build().unwrap();
+        let parallel = builder.parallel(true).build().unwrap();
+
+        assert_eq!(serial, parallel);
+    }
+
+    // The first error is reported, as with serial parsing
+    let dic_content = "3\nfoo/A\nbar/\u{1F600}\nbaz/\u{1F600}";
+    let builder = DictBuilder::new()
+        .config_str("FLAG long")
+        .dict_str(dic_content);
+    assert_eq!(
+        builder.clone().parallel(false).build().unwrap_err(),
+        builder.parallel(true).build().unwrap_err()
+    );
+}
+
+#[test]
+fn test_update_words() {
+    let affix_str = indoc! {"
+        FORBIDDENWORD !
+
+        SFX S Y 1
+        SFX S 0 s .
+    "};
+    let dict_str = indoc! {"
+        cat/S
+        dog/S
+        nope/!S
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let mut dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str(dict_str)
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        // Adding with flags creates affixed forms
+        dict.add_word_with_flags("bird", "S").unwrap();
+        assert!(dict.check("bird birds Birds"), "{strategy:?}");
+        assert!(dict.stems.contains("bird"));
+        assert!(dict.add_word_with_flags("fish", "SS").is_ok());
+
+        dict.add_word("zspell");
+        assert!(dict.check_word("zspell"));
+        assert!(!dict.check_word("zspells"));
+
+        // Removing a stem removes its affixed forms, and only those
+        assert!(dict.remove_word("cat"));
+        assert!(!dict.check_word("cat"));
+        assert!(!dict.check_word("cats"));
+        assert!(dict.check_word("dogs"));
+        assert!(!dict.stems.contains("cat"));
+        assert!(!dict.remove_word("cat"));
+
+        // Forbidding and allowing again
+        dict.forbid_word("dog");
+        assert!(!dict.check_word("dog"));
+        assert!(dict.entry("dog").forbidden());
+        assert!(dict.check_word("dogs"));
+        dict.add_word("dog");
+        assert!(dict.check_word("dog"));
+
+        // Forbidden forms of a removed stem are removed too
+        assert!(dict.entry("nopes").forbidden());
+        assert!(dict.remove_word("nope"));
+        assert!(!dict.entry("nopes").forbidden());
+        assert!(!dict.check_word("nopes"));
+    }
+
+    // A form that is also its own stem keeps the other stem's metas
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let mut dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str("apple/S\napples")
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        assert!(dict.remove_word("apples"), "{strategy:?}");
+        assert!(dict.check_word("apples"), "{strategy:?}");
+        let entry = dict.entry("apples");
+        assert_eq!(entry.stems().unwrap().collect::<Vec<_>>(), ["apple"]);
+
+        assert!(dict.remove_word("apple"), "{strategy:?}");
+        assert!(!dict.check("apple apples"), "{strategy:?}");
+    }
+
+    let mut dict = DictBuilder::new().config_str("FLAG num").build().unwrap();
+    assert!(dict.add_word_with_flags("fish", "abc").is_err());
+}
+
+#[test]
+fn test_personal_roundtrip() {
+    let affix_str = indoc! {"
+        SFX S Y 1
+        SFX S 0 s .
+    "};
+    let personal_str = indoc! {"
+        *ijkl
+        abcd po:verb
+        banana/apple
+        efgh st:something po:noun
+    "};
+
+    let build = |personal: &str| {
+        DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str("apple/S")
+            .personal_str(personal)
+            .build()
+            .unwrap()
+    };
+
+    let mut dict = build(personal_str);
+    assert_eq!(dict.to_personal_string(), personal_str);
+
+    dict.add_word("zspell");
+    dict.forbid_word("teh");
+    dict.add_personal_str("mnop/apple").unwrap();
+    assert!(dict.check_word("mnops"));
+    let written = dict.to_personal_string();
+    assert_eq!(build(&written).to_personal_string(), written);
+    assert!(written.contains("\nzspell\n"));
+    assert!(written.contains("*teh\n"));
+    assert!(written.contains("\nmnop/apple\n"));
+
+    // Slashes within words are escaped
+    dict.add_word("km/h");
+    dict.forbid_word("w/o");
+    let written = dict.to_personal_string();
+    assert!(written.contains("\nkm\\/h\n"));
+    assert!(written.contains("*w\\/o\n"));
+    let reloaded = build(&written);
+    assert!(reloaded.check_word("km/h"));
+    assert!(reloaded.entry("w/o").forbidden());
+    assert_eq!(reloaded.to_personal_string(), written);
+
+    // Words from the dictionary file are not included
+    assert!(!written.lines().any(|line| line == "apple"));
+    assert_eq!(build("").to_personal_string(), "");
+}
+
+#[test]
+fn test_dictionary_stack() {
+    let affix_str = indoc! {"
+        SFX S Y 1
+        SFX S 0 s .
+    "};
+    let base = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str("apple/S\nbanana/S po:noun\ncolour/S")
+        .build()
+        .unwrap();
+    let domain = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str("banana/S st:fruit\nzspell/S")
+        .personal_str("*colour\n*colours")
+        .build()
+        .unwrap();
+
+    let mut stack = DictionaryStack::new();
+    assert!(!stack.check_word("apple"));
+    stack.push(base);
+    stack.push(domain);
+
+    assert!(stack.check("apples bananas zspells"));
+    // Forbidden in a higher layer
+    assert!(!stack.check_word("colour"));
+    assert!(stack.entry("colours").forbidden());
+    let errors: Vec<_> = stack.check_indices("apple colour ffff").collect();
+    assert_eq!(errors, [(6, "colour"), (13, "ffff")]);
+
+    // A higher layer can also accept what a lower one forbids
+    stack.push_word_list("colour\n\n  Hunspell  \n");
+    assert!(stack.check("colour Hunspell"));
+    assert!(!stack.check_word("colours"));
+    assert!(!stack.check_word("Hunspells"));
+
+    // Results from all accepting layers are merged
+    let entry = stack.entry("bananas");
+    assert!(entry.correct());
+    assert_eq!(entry.stems().unwrap(), ["banana", "fruit"]);
+    let analysis = entry.analyze().unwrap();
+    assert!(analysis.contains(&&MorphInfo::Stem("fruit".into())));
+    assert!(analysis.contains(&&MorphInfo::Part(crate::PartOfSpeech::Noun)));
+
+    let entries: Vec<_> = stack.entries("apple qqq").collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].index(), 6);
+    assert!(entries[1].stems().is_none());
+
+    stack.layers_mut()[0].add_word("qqq");
+    assert!(stack.check("apple qqq"));
+    assert_eq!(stack.layers().len(), 3);
+}
+
+#[test]
+#[cfg(feature = "unstable-suggestions")]
+fn test_dictionary_stack_suggest() {
+    let base = DictBuilder::new()
+        .config_str("")
+        .dict_str("wall\nwell")
+        .build()
+        .unwrap();
+    let domain = DictBuilder::new()
+        .config_str("")
+        .dict_str("wel")
+        .personal_str("*well")
+        .build()
+        .unwrap();
+
+    let mut stack = DictionaryStack::new();
+    stack.push(base);
+    stack.push(domain);
+
+    let mut sugg = stack.entry("wll").suggest().unwrap();
+    sugg.sort_unstable();
+    // "well" is forbidden by the domain layer
+    assert_eq!(sugg, ["wall", "wel"]);
+    assert!(stack.entry("wall").suggest().is_none());
+}
+
+#[test]
+fn test_word_list() {
+    let dict = DictBuilder::new()
+        .word_list_str("apple\n\n  banana \nHashMap\n")
+        .build()
+        .unwrap();
+
+    assert!(dict.check("apple banana HashMap Apple"));
+    assert!(!dict.check_word("hashmap"));
+    assert!(!dict.check_word("apples"));
+    let entry = dict.entry("banana");
+    assert_eq!(entry.stems().unwrap().collect::<Vec<_>>(), ["banana"]);
+    assert_eq!(entry.analyze().unwrap().count(), 0);
+    // Raw words are not personal words
+    assert_eq!(dict.to_personal_string(), "");
+
+    // A word list combined with an affix file, and attached afterwards
+    let mut dict = DictBuilder::new()
+        .config_str("SFX S Y 1\nSFX S 0 s .")
+        .dict_str("apple/S\nteh")
+        .word_list_str("Vec")
+        .personal_str("*teh")
+        .build()
+        .unwrap();
+    assert!(dict.check("apples Vec"));
+    assert!(!dict.check_word("Vecs"));
+
+    dict.add_word_list("teh\nBTreeMap");
+    assert!(dict.check_word("BTreeMap"));
+    assert!(!dict.check_word("teh"));
+    assert!(dict.remove_word("BTreeMap"));
+    assert!(!dict.check_word("BTreeMap"));
+
+    // Without an affix file, a dictionary file still needs one
+    let res = DictBuilder::new().dict_str("apple").build();
+    assert!(matches!(
+        res,
+        Err(Error::Build(BuildError::BuilderCfgUnspecified))
+    ));
+}
+
+#[test]
+fn test_word_list_compact() {
+    let bytes = DictBuilder::new()
+        .word_list_str("apple\nbanana")
+        .build_compact()
+        .unwrap();
+    let compact = CompactDictionary::new(bytes).unwrap();
+    assert!(compact.check("apple banana"));
+    assert!(!compact.check_word("cherry"));
+}
+
+#[test]
+fn test_multi_dictionary() {
+    let en = DictBuilder::new()
+        .config_str("LANG en_US\nSFX S Y 1\nSFX S 0 s .")
+        .dict_str("the/S\ndocument/S\nquote/S\nsay/S\nand\nzspell")
+        .build()
+        .unwrap();
+    let de = DictBuilder::new()
+        .config_str("LANG de_DE")
+        .dict_str("alles\nist\ngut\nund")
+        .build()
+        .unwrap();
+    let fr = DictBuilder::new()
+        .config_str("")
+        .dict_str("oui\nc'est\nvrai")
+        .build()
+        .unwrap();
+
+    let mut multi = MultiDictionary::new();
+    assert!(multi.best_fit("anything").is_none());
+    assert!(multi.insert(en).is_none());
+    assert!(multi.insert(de).is_none());
+    assert!(multi.insert_as("fr", fr).is_none());
+    assert_eq!(
+        multi.languages().collect::<Vec<_>>(),
+        ["en_US", "de_DE", "fr"]
+    );
+    assert!(multi.get("de_DE").unwrap().check_word("gut"));
+    assert!(multi.get("nl").is_none());
+
+    assert_eq!(multi.best_fit("alles ist gut"), Some("de_DE"));
+    assert_eq!(multi.best_fit("c'est vrai"), Some("fr"));
+    // Ties go to the first dictionary
+    assert_eq!(multi.best_fit("qqq"), Some("en_US"));
+
+    let input = "The document says zspel. Alles ist gutt! Oui, c'est vria.";
+    let errors = multi.check(input);
+    let found: Vec<_> = errors
+        .iter()
+        .map(|e| (e.index(), e.word(), e.lang()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (18, "zspel", "en_US"),
+            (35, "gutt", "de_DE"),
+            (52, "vria", "fr")
+        ]
+    );
+
+    // "und" would otherwise be checked in German
+    let input = "Quotes and und, the end";
+    let errors = multi.check_spans(input, &[(11..14, "en_US")]).unwrap();
+    let found: Vec<_> = errors
+        .iter()
+        .map(|e| (e.index(), e.word(), e.lang()))
+        .collect();
+    assert_eq!(found, [(11, "und", "en_US"), (20, "end", "en_US")]);
+
+    assert_eq!(
+        multi.check_spans(input, &[(0..6, "nl")]),
+        Err(Error::UnknownLanguage("nl".to_owned()))
+    );
+
+    // Bad spans are errors rather than panics or duplicate results
+    let spans = [(0..6, "en_US"), (4..10, "de_DE")];
+    assert_eq!(
+        multi.check_spans(input, &spans),
+        Err(Error::InvalidSpan(4..10))
+    );
+    assert_eq!(
+        multi.check_spans(input, &[(20..40, "en_US")]),
+        Err(Error::InvalidSpan(20..40))
+    );
+    assert_eq!(
+        multi.check_spans("où", &[(0..2, "fr")]),
+        Err(Error::InvalidSpan(0..2))
+    );
+
+    multi.get_mut("fr").unwrap().add_word("vria");
+    assert!(multi.check("Oui, c'est vria.").is_empty());
+    assert!(multi.remove("fr").is_some());
+    assert!(multi.remove("fr").is_none());
+}
+
+#[test]
+fn test_unmunch() {
+    let affix_str = indoc! {"
+        NOSUGGEST !
+        FORBIDDENWORD *
+        SUBSTANDARD ~
+        OCONV 1
+        OCONV ' ’
+
+        PFX U Y 1
+        PFX U 0 un .
+
+        SFX S Y 1
+        SFX S 0 s .
+
+        SFX D Y 1
+        SFX D 0 ed/S .
+
+        SFX N Y 1
+        SFX N 0 's/~ .
+    "};
+    let dict_str = indoc! {"
+        7
+        do/UD
+        apple/SN
+        damn/!S
+        irregardless/*
+        ain't/~
+        rare/S
+        o'clock
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str(dict_str)
+            .personal_str("*rares")
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        let mut forms: Vec<_> = dict
+            .unmunch()
+            .map(|form| {
+                let mut rules: Vec<_> = form.flags().iter().map(|f| format!("{f:?}")).collect();
+                rules.sort_unstable();
+                (
+                    form.word().to_owned(),
+                    form.stem().to_owned(),
+                    rules,
+                    form.kind(),
+                )
+            })
+            .collect();
+        forms.sort_unstable_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+        let expected = [
+            ("ain’t", "ain’t", vec![], FormKind::Substandard),
+            ("apple", "apple", vec![], FormKind::Accepted),
+            ("apples", "apple", vec!["S"], FormKind::Accepted),
+            ("apple’s", "apple", vec!["N"], FormKind::Substandard),
+            ("damn", "damn", vec![], FormKind::NoSuggest),
+            ("damns", "damn", vec!["S"], FormKind::NoSuggest),
+            ("do", "do", vec![], FormKind::Accepted),
+            ("doed", "do", vec!["D"], FormKind::Accepted),
+            ("doeds", "do", vec!["D", "S"], FormKind::Accepted),
+            ("irregardless", "irregardless", vec![], FormKind::Forbidden),
+            ("o’clock", "o’clock", vec![], FormKind::Accepted),
+            ("rare", "rare", vec![], FormKind::Accepted),
+            // Forbidden by the personal dictionary, so never accepted
+            ("rares", "rares", vec![], FormKind::Forbidden),
+            ("undo", "do", vec!["U"], FormKind::Accepted),
+            ("undoed", "do", vec!["D", "U"], FormKind::Accepted),
+            ("undoeds", "do", vec!["D", "S", "U"], FormKind::Accepted),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(word, stem, rules, kind)| {
+                let rules: Vec<String> = rules.into_iter().map(Into::into).collect();
+                (word.to_owned(), stem.to_owned(), rules, kind)
+            })
+            .collect();
+
+        assert_eq!(forms, expected, "{strategy:?}");
+    }
+}
+
+#[test]
+fn test_munch() {
+    let affix_str = indoc! {"
+        PFX U Y 1
+        PFX U 0 un .
+
+        SFX S Y 2
+        SFX S 0 s [^y]
+        SFX S y ies y
+
+        SFX D Y 1
+        SFX D 0 ed/S .
+
+        SFX G N 1
+        SFX G 0 ing .
+    "};
+    let words = indoc! {"
+        lock
+        locks
+        unlock
+        unlocks
+        locked
+        lockeds
+        unlocked
+        unlockeds
+        talk
+        talks
+        talking
+        fly
+        flies
+        flying
+        unfly
+        sky
+    "};
+
+    let dict = DictBuilder::new().config_str(affix_str).build().unwrap();
+    let munched = dict.munch(words);
+    assert_eq!(munched, "5\nfly/GS\nlock/DSU\nsky\ntalk/GS\nunfly\n");
+
+    // The result creates exactly the original words
+    let roundtrip = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str(&munched)
+        .build()
+        .unwrap();
+    let mut generated: Vec<_> = roundtrip
+        .unmunch()
+        .map(|form| form.word().to_owned())
+        .collect();
+    generated.sort_unstable();
+    let mut expected: Vec<_> = words.lines().collect();
+    expected.sort_unstable();
+    assert_eq!(generated, expected);
+
+    // Numeric flags are separated with commas
+    let dict = DictBuilder::new()
+        .config_str("FLAG num\nSFX 10 Y 1\nSFX 10 0 s .\nSFX 20 Y 1\nSFX 20 0 ed .")
+        .build()
+        .unwrap();
+    assert_eq!(dict.munch("walk\nwalks\nwalked\n\n"), "1\nwalk/10,20\n");
+    assert_eq!(dict.munch(""), "0\n");
+
+    // Slashes in stems are escaped, and input conversions are applied to words
+    let affix_str = "ICONV 1\nICONV ’ '\nSFX S Y 1\nSFX S 0 s .";
+    let dict = DictBuilder::new().config_str(affix_str).build().unwrap();
+    let munched = dict.munch("km/h\nkm/hs\nit’s\nit's");
+    assert_eq!(munched, "2\nit's\nkm\\/h/S\n");
+    let roundtrip = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str(&munched)
+        .build()
+        .unwrap();
+    for word in ["km/h", "km/hs", "it’s"] {
+        assert!(roundtrip.check_word(word), "{word}");
+    }
+
+    // With `AF`, flags are written as alias indices
+    let affix_str = indoc! {"
+        AF 3
+        AF S
+        AF SD
+        AF SX
+
+        SFX S Y 1
+        SFX S 0 s .
+
+        SFX D Y 1
+        SFX D 0 ed .
+    "};
+    let dict = DictBuilder::new().config_str(affix_str).build().unwrap();
+    let munched = dict.munch("walk\nwalks\nwalked\ncat\ncats");
+    assert_eq!(munched, "2\ncat/1\nwalk/2\n");
+    let roundtrip = DictBuilder::new()
+        .config_str(affix_str)
+        .dict_str(&munched)
+        .build()
+        .unwrap();
+    for word in ["walk", "walks", "walked", "cat", "cats"] {
+        assert!(roundtrip.check_word(word), "{word}");
+    }
+    assert!(!roundtrip.check_word("cated"));
+}
+
+#[test]
+fn test_generate() {
+    let affix_str = indoc! {"
+        FORBIDDENWORD *
+        SUBSTANDARD ~
+        OCONV 1
+        OCONV a A
+
+        SFX S Y 1
+        SFX S 0 s . is:plural
+
+        SFX D Y 1
+        SFX D 0 ed/S . is:past
+
+        SFX Z Y 1
+        SFX Z 0 z/~ . is:plural
+    "};
+    let dict_str = indoc! {"
+        5
+        cat/SZ po:noun
+        drink/D po:verb
+        drank st:drink po:verb is:past
+        drinkz/* st:drink is:plural
+        mouse/S po:noun
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str(dict_str)
+            .personal_str("*mouses")
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        let plural = MorphInfo::from("is:plural");
+        let past = MorphInfo::from("is:past");
+        let noun = MorphInfo::from("po:noun");
+
+        // "catz" is substandard
+        assert_eq!(
+            dict.generate("cat", std::slice::from_ref(&plural)),
+            ["cAts"],
+            "{strategy:?}"
+        );
+        assert_eq!(
+            dict.generate("cat", &[plural.clone(), noun.clone()]),
+            ["cAts"],
+            "{strategy:?}"
+        );
+        assert_eq!(
+            dict.generate("drink", std::slice::from_ref(&past)),
+            ["drAnk", "drinked", "drinkeds"],
+            "{strategy:?}"
+        );
+        // Twofold suffixes combine their morphs, "drinkz" is forbidden
+        assert_eq!(
+            dict.generate("drink", &[past.clone(), plural.clone()]),
+            ["drinkeds"],
+            "{strategy:?}"
+        );
+        assert!(dict.generate("cat", std::slice::from_ref(&past)).is_empty());
+        // Forbidden by the personal dictionary
+        assert!(dict.generate("mouse", &[noun, plural]).is_empty());
+        assert!(dict.generate("dog", &[]).is_empty());
+
+        // Irregular forms are still found after loading from a cache
+        let mut dict = cache::read(&cache::write(&dict, affix_str, 0), 0).unwrap();
+        assert_eq!(
+            dict.generate("drink", std::slice::from_ref(&past)),
+            ["drAnk", "drinked", "drinkeds"],
+            "{strategy:?}"
+        );
+
+        dict.remove_word("drank");
+        assert_eq!(
+            dict.generate("drink", &[past]),
+            ["drinked", "drinkeds"],
+            "{strategy:?}"
+        );
+    }
+}
+
+#[test]
+fn test_generate_homonyms() {
+    let affix_str = indoc! {"
+        SFX S Y 1
+        SFX S 0 s . is:plural
+
+        SFX V Y 1
+        SFX V 0 s . is:sg_3
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str("drink/S po:noun\ndrink/V po:verb")
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        let noun = MorphInfo::from("po:noun");
+        let verb = MorphInfo::from("po:verb");
+        let sg_3 = MorphInfo::from("is:sg_3");
+
+        // Fields must come from the same homonym
+        assert_eq!(
+            dict.generate("drink", &[verb, sg_3.clone()]),
+            ["drinks"],
+            "{strategy:?}"
+        );
+        assert!(
+            dict.generate("drink", &[noun, sg_3]).is_empty(),
+            "{strategy:?}"
+        );
+    }
+}
+
+#[test]
+fn test_analyses() {
+    let affix_str = indoc! {"
+        PFX U Y 1
+        PFX U 0 un . dp:un
+
+        SFX X Y 1
+        SFX X 0 able/S . ds:able
+
+        SFX S Y 1
+        SFX S 0 s . is:plural
+    "};
+    let dict_str = indoc! {"
+        3
+        drink/UX po:verb
+        drank st:drink po:verb is:past
+        drink/S po:noun
+    "};
+
+    for strategy in [Strategy::Eager, Strategy::Lazy] {
+        let dict = DictBuilder::new()
+            .config_str(affix_str)
+            .dict_str(dict_str)
+            .expansion(strategy)
+            .build()
+            .unwrap();
+
+        let lines = |word: &str| -> Option<Vec<String>> {
+            let entry = dict.entry(word);
+            let analyses = entry.analyses()?;
+            let mut ret: Vec<String> = analyses
+                .iter()
+                .map(|analysis| {
+                    let fields: Vec<_> = analysis.iter().map(ToString::to_string).collect();
+                    fields.join(" ")
+                })
+                .collect();
+            ret.sort_unstable();
+            Some(ret)
+        };
+
+        assert_eq!(
+            lines("drink").unwrap(),
+            ["st:drink po:noun", "st:drink po:verb"],
+            "{strategy:?}"
+        );
+        assert_eq!(lines("drank").unwrap(), ["st:drink po:verb is:past"]);
+        assert_eq!(
+            lines("undrinkables").unwrap(),
+            ["st:drink po:verb dp:un ds:able is:plural"],
+            "{strategy:?}"
+        );
+        assert!(lines("drinker").is_none());
+    }
+}
@@ -1,12 +1,13 @@
 use pretty_assertions::assert_eq;
 
 use super::*;
+use crate::affix::PartOfSpeech;
 
 #[test]
 fn test_dict_entry_ok() {
-    let f1 = FlagType::Utf8;
-    let f2 = FlagType::Ascii;
-    let f3 = FlagType::Long;
+    let f1 = &ParsedCfg::load_from_str("FLAG UTF-8").unwrap();
+    let f2 = &ParsedCfg::load_from_str("").unwrap();
+    let f3 = &ParsedCfg::load_from_str("FLAG long").unwrap();
 
     let s_0f0m_1 = "abcd";
     let s_0f0m_2 = "abcd # comment";
@@ -96,6 +97,59 @@ fn test_dict_entry_ok() {
     assert_eq!(DictEntry::parse_single(s_0f2m_2, f3, 0), Ok(r_0f2m));
 }
 
+#[test]
+fn test_dict_entry_escaped_slash() {
+    let cfg = &ParsedCfg::load_from_str("").unwrap();
+
+    let entry = DictEntry::new("km/h", &[Flag::new_ascii(b'A')], &[]);
+    assert_eq!(DictEntry::parse_single("km\\/h/A", cfg, 0), Ok(entry));
+
+    let entry = DictEntry::new("and/or", &[], &[]);
+    assert_eq!(DictEntry::parse_single("and\\/or", cfg, 0), Ok(entry));
+}
+
+#[test]
+fn test_dict_entry_affix_alias() {
+    let cfg = ParsedCfg::load_from_str("FLAG long\nAF 2\nAF AaBb\nAF Cc").unwrap();
+
+    let expected = DictEntry::new(
+        "abcd",
+        &[Flag::new_long("Aa"), Flag::new_long("Bb")],
+        &[MorphInfo::Stem("ab".into())],
+    );
+    assert_eq!(
+        DictEntry::parse_single("abcd/1 st:ab", &cfg, 0),
+        Ok(expected)
+    );
+
+    let expected = DictEntry::new("abcd", &[Flag::new_long("Cc")], &[]);
+    assert_eq!(DictEntry::parse_single("abcd/2", &cfg, 0), Ok(expected));
+
+    assert!(DictEntry::parse_single("abcd/3", &cfg, 0).is_err());
+    assert!(DictEntry::parse_single("abcd/Aa", &cfg, 0).is_err());
+}
+
+#[test]
+fn test_dict_entry_morph_alias() {
+    let cfg = ParsedCfg::load_from_str("AM 2\nAM po:noun is:pl\nAM st:ab").unwrap();
+    let morph = [
+        MorphInfo::Part(PartOfSpeech::Noun),
+        MorphInfo::InflecSfx("pl".into()),
+    ];
+
+    let expected = DictEntry::new("abcd", &[Flag::new_utf8('A')], &morph);
+    assert_eq!(DictEntry::parse_single("abcd/A\t1", &cfg, 0), Ok(expected));
+
+    let expected = DictEntry::new("abcd", &[], &[MorphInfo::Stem("ab".into())]);
+    assert_eq!(DictEntry::parse_single("abcd 2", &cfg, 0), Ok(expected));
+
+    // Non-numeric morph info is still parsed as-is
+    let expected = DictEntry::new("abcd", &[], &[MorphInfo::Stem("xy".into())]);
+    assert_eq!(DictEntry::parse_single("abcd st:xy", &cfg, 0), Ok(expected));
+
+    assert!(DictEntry::parse_single("abcd/A 3", &cfg, 0).is_err());
+}
+
 #[test]
 fn test_personal_entry_ok() {
     let s1 = "abcd # comment";
@@ -120,4 +174,7 @@ fn test_personal_entry_ok() {
     assert_eq!(PersonalEntry::parse_single(s2), r2);
     assert_eq!(PersonalEntry::parse_single(s3), r3);
     assert_eq!(PersonalEntry::parse_single(s4), r4);
+
+    let r5 = PersonalEntry::new("km/h", Some("a/b"), Vec::new(), true);
+    assert_eq!(PersonalEntry::parse_single(r"*km\/h/a\/b"), r5);
 }
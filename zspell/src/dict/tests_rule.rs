@@ -38,15 +38,42 @@ fn test_apply_pattern() {
     let mut rule = AfxRulePattern::new("zzz", Some("y"));
 
     rule.set_pattern("[^aeiou]y", kind).unwrap();
-    assert_eq!(rule.apply_pattern("xxxy", kind), Some("xxxzzz".to_owned()));
+    assert_eq!(
+        rule.apply_pattern("xxxy", kind, false),
+        Some("xxxzzz".to_owned())
+    );
 
     kind = RuleType::Prefix;
     rule.set_pattern("y[^aeiou]", kind).unwrap();
-    assert_eq!(rule.apply_pattern("yxxx", kind), Some("zzzxxx".to_owned()));
+    assert_eq!(
+        rule.apply_pattern("yxxx", kind, false),
+        Some("zzzxxx".to_owned())
+    );
 
     kind = RuleType::Suffix;
     rule.set_pattern(".", kind).unwrap();
-    assert_eq!(rule.apply_pattern("xxx", kind), Some("xxxzzz".to_owned()));
+    assert_eq!(
+        rule.apply_pattern("xxx", kind, false),
+        Some("xxxzzz".to_owned())
+    );
+}
+
+#[test]
+fn test_apply_pattern_full_strip() {
+    let kind = RuleType::Suffix;
+    let mut rule = AfxRulePattern::new("vado", Some("andare"));
+    rule.set_pattern("andare", kind).unwrap();
+
+    // Stripping the entire stem is only allowed with `FULLSTRIP`
+    assert_eq!(rule.apply_pattern("andare", kind, false), None);
+    assert_eq!(
+        rule.apply_pattern("andare", kind, true),
+        Some("vado".to_owned())
+    );
+    assert_eq!(
+        rule.apply_pattern("riandare", kind, false),
+        Some("rivado".to_owned())
+    );
 }
 
 // #[test]
@@ -0,0 +1,359 @@
+//! Expand a dictionary into every word form it accepts, like Hunspell's `unmunch`, and
+//! generate forms with a given morphology
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::meta::{Meta, Source};
+use super::rules_apply::{create_affixed_word_map, AfxContext};
+use super::rules_reverse::LazyStem;
+use super::{split_derivations, Dictionary, EntryRules, Flag, FlagValue, WordCtx, WordList};
+use crate::morph::MorphInfo;
+
+/// Which wordlist a [`WordForm`] belongs to
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FormKind {
+    /// Accepted and suggested
+    Accepted,
+    /// Accepted but never suggested (`NOSUGGEST`)
+    NoSuggest,
+    /// Accepted but never suggested, because every way of creating the word is
+    /// `SUBSTANDARD`
+    Substandard,
+    /// Rejected (`FORBIDDENWORD` or a personal dictionary)
+    Forbidden,
+}
+
+/// A single word created from a dictionary stem, returned by [`Dictionary::unmunch`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordForm<'dict> {
+    word: Cow<'dict, str>,
+    stem: Arc<str>,
+    flags: Vec<Flag>,
+    kind: FormKind,
+}
+
+impl WordForm<'_> {
+    /// The word, with output conversions (`OCONV`) applied
+    #[inline]
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    /// The dictionary stem the word was created from, with output conversions applied
+    #[inline]
+    pub fn stem(&self) -> &str {
+        &self.stem
+    }
+
+    /// Flags of the affix rules that were applied to the stem to create this word. Empty
+    /// if the word is the stem itself.
+    #[inline]
+    pub fn flags(&self) -> &[Flag] {
+        &self.flags
+    }
+
+    /// Whether the word is accepted, accepted but not suggested, substandard, or
+    /// forbidden
+    #[inline]
+    pub fn kind(&self) -> FormKind {
+        self.kind
+    }
+}
+
+/// All word forms of a dictionary. Forms that are forbidden by another entry are only
+/// reported as forbidden, since they are never accepted.
+pub(super) fn word_forms(dict: &Dictionary) -> impl Iterator<Item = WordForm<'_>> {
+    let lists = [
+        (&dict.wordlist, FormKind::Accepted),
+        (&dict.wordlist_nosuggest, FormKind::NoSuggest),
+        (&dict.wordlist_forbidden, FormKind::Forbidden),
+    ];
+
+    let stored = lists.into_iter().flat_map(move |(list, kind)| {
+        list.0.iter().flat_map(move |(word, metas)| {
+            let kind = match kind {
+                FormKind::Forbidden => kind,
+                _ if is_forbidden(dict, word) => return Vec::new(),
+                _ if is_substandard(dict, word) => FormKind::Substandard,
+                _ => kind,
+            };
+            let word = dict.parsed_config.convert_output(word);
+            group_by_stem(dict, &word, metas, kind)
+        })
+    });
+
+    // With `Strategy::Lazy`, affixed words only exist as rules on their stems
+    let lazy = dict
+        .lazy
+        .iter()
+        .flat_map(|index| index.stems())
+        .flat_map(move |(stem, entries)| {
+            entries
+                .iter()
+                .flat_map(move |entry| expand_lazy(dict, stem, entry))
+        });
+
+    stored.chain(lazy)
+}
+
+/// Create one form per stem that a word's metadata refers to
+fn group_by_stem<'a>(
+    dict: &Dictionary,
+    word: &Cow<'a, str>,
+    metas: &[Meta],
+    kind: FormKind,
+) -> Vec<WordForm<'a>> {
+    let mut ret: Vec<WordForm<'a>> = Vec::with_capacity(1);
+
+    for meta in metas {
+        let stem = output_stem(dict, meta.stem_rc());
+        let idx = ret
+            .iter()
+            .position(|form| form.stem == stem)
+            .unwrap_or_else(|| {
+                ret.push(WordForm {
+                    word: word.clone(),
+                    stem,
+                    flags: Vec::new(),
+                    kind,
+                });
+                ret.len() - 1
+            });
+
+        if let Source::Affix { rule, .. } = meta.source() {
+            let flag = dict
+                .affix_flags
+                .iter()
+                .find_map(|(flag, value)| match value {
+                    FlagValue::Rule(r) if Arc::ptr_eq(r, rule) => Some(*flag),
+                    _ => None,
+                });
+            let flags = &mut ret[idx].flags;
+            if let Some(flag) = flag.filter(|f| !flags.contains(f)) {
+                flags.push(flag);
+            }
+        }
+    }
+
+    ret
+}
+
+/// Apply output conversions to a stem, only allocating if it changes
+fn output_stem(dict: &Dictionary, stem: &Arc<str>) -> Arc<str> {
+    match dict.parsed_config.convert_output(stem) {
+        Cow::Borrowed(_) => Arc::clone(stem),
+        Cow::Owned(converted) => converted.into(),
+    }
+}
+
+/// Whether a word is rejected, following the same rules as checking it
+fn is_forbidden(dict: &Dictionary, word: &str) -> bool {
+    dict.wordlist_forbidden.0.contains_key(word)
+        || dict
+            .wordlist_forbidden
+            .0
+            .contains_key(word.to_lowercase().as_str())
+        || matches!(
+            dict.lazy_ctx(word, word),
+            Some(WordCtx::Incorrect { forbidden: true })
+        )
+}
+
+/// Whether every way of creating an accepted word is substandard
+fn is_substandard(dict: &Dictionary, word: &str) -> bool {
+    let stored = dict.wordlist.0.contains_key(word) || dict.wordlist_nosuggest.0.contains_key(word);
+    if stored && !dict.substandard.contains(word) {
+        return false;
+    }
+
+    // With `Strategy::Lazy`, other stems may create the same word
+    match dict.lazy_ctx(word, word) {
+        Some(WordCtx::Correct { substandard, .. }) => substandard,
+        _ => stored,
+    }
+}
+
+/// Create the affixed words for a stem stored with [`Strategy::Lazy`](super::Strategy::Lazy).
+/// The stem itself is already in a wordlist.
+fn expand_lazy<'a>(dict: &'a Dictionary, stem: &Arc<str>, entry: &LazyStem) -> Vec<WordForm<'a>> {
+    let (kind, words, substandard_words) = lazy_words(dict, stem, entry);
+
+    let words = words.0.into_iter().map(|(w, m)| (w, m, false));
+    let from_substandard = substandard_words.0.into_iter().map(|(w, m)| (w, m, true));
+
+    words
+        .chain(from_substandard)
+        .flat_map(|(word, metas, substandard)| {
+            let kind = match kind {
+                FormKind::Forbidden => kind,
+                _ if is_forbidden(dict, &word) => return Vec::new(),
+                // A standard form of the same word is reported by its own stem
+                _ if substandard && !is_substandard(dict, &word) => return Vec::new(),
+                _ if substandard => FormKind::Substandard,
+                _ => kind,
+            };
+            let word = match dict.parsed_config.convert_output(&word) {
+                Cow::Borrowed(_) => Cow::Owned(word.into_string()),
+                Cow::Owned(converted) => Cow::Owned(converted),
+            };
+            group_by_stem(dict, &word, &metas, kind)
+        })
+        .collect()
+}
+
+/// Affixed words of a lazy stem with their metadata, and whether they are forbidden,
+/// nosuggest or accepted. Words that are substandard are returned separately, unless
+/// they are forbidden.
+fn lazy_words(
+    dict: &Dictionary,
+    stem: &Arc<str>,
+    entry: &LazyStem,
+) -> (FormKind, WordList, WordList) {
+    let attrs = entry.attrs;
+    let kind = if attrs.forbid {
+        FormKind::Forbidden
+    } else if attrs.nosuggest {
+        FormKind::NoSuggest
+    } else {
+        FormKind::Accepted
+    };
+
+    let (pfx_rules, sfx_rules): (Vec<_>, Vec<_>) = entry.rules.iter().partition(|r| r.is_pfx());
+    let ctx = AfxContext {
+        affix_flags: &dict.affix_flags,
+        rule_flags: &dict.rule_flags,
+        complex_prefixes: dict.parsed_config.complex_prefixes(),
+    };
+
+    let mut words = WordList::new();
+    let mut substandard_words = WordList::new();
+    create_affixed_word_map(
+        stem,
+        &pfx_rules,
+        &sfx_rules,
+        &ctx,
+        entry.dict_meta.as_ref(),
+        &mut words,
+        &mut substandard_words,
+    );
+
+    if kind == FormKind::Forbidden {
+        for (word, metas) in substandard_words.0.drain() {
+            words.0.entry(word).or_default().extend(metas);
+        }
+    } else if attrs.substandard {
+        // Every word from a substandard stem is substandard
+        for (word, metas) in words.0.drain() {
+            substandard_words.0.entry(word).or_default().extend(metas);
+        }
+    }
+
+    (kind, words, substandard_words)
+}
+
+/// Find the accepted words created from `stem` whose analysis includes every item in
+/// `morphs`. Forbidden and substandard words are never generated.
+///
+/// Only the entries for `stem` and the entries that name it with `st:` are expanded.
+pub(super) fn generate<'a>(
+    dict: &'a Dictionary,
+    stem: &str,
+    morphs: &[MorphInfo],
+) -> Vec<Cow<'a, str>> {
+    let stem = dict.parsed_config.normalize_input(stem);
+    let stem = stem.as_ref();
+    let mut ret: Vec<Cow<'a, str>> = Vec::new();
+
+    let alt_stems = dict.alt_stems.get(stem).into_iter().flatten();
+    let sources = dict.stems.get(stem).into_iter().chain(alt_stems);
+
+    for source in sources {
+        for word in eager_words(dict, source) {
+            let Some((word, metas)) = dict
+                .wordlist
+                .0
+                .get_key_value(word.as_str())
+                .or_else(|| dict.wordlist_nosuggest.0.get_key_value(word.as_str()))
+            else {
+                continue;
+            };
+            if !is_forbidden(dict, word)
+                && !is_substandard(dict, word)
+                && metas_match(metas, stem, morphs)
+            {
+                ret.push(dict.parsed_config.convert_output(word));
+            }
+        }
+
+        // With `Strategy::Lazy`, affixed words only exist as rules on their stems
+        let entries = dict.lazy.iter().flat_map(|index| index.get(source));
+        for entry in entries {
+            let (kind, words, _) = lazy_words(dict, source, entry);
+            if kind == FormKind::Forbidden {
+                continue;
+            }
+
+            for (word, metas) in words.0 {
+                if !is_forbidden(dict, &word) && metas_match(&metas, stem, morphs) {
+                    let word = dict.parsed_config.convert_output(&word).into_owned();
+                    ret.push(Cow::Owned(word));
+                }
+            }
+        }
+    }
+
+    ret.sort_unstable();
+    ret.dedup();
+    ret
+}
+
+/// The stem and the words its flags may create. These are only candidates, they are
+/// looked up in our wordlists to find how they were actually created. With
+/// `Strategy::Lazy`, affixed words are not stored so only the stem is returned.
+fn eager_words(dict: &Dictionary, stem: &Arc<str>) -> Vec<String> {
+    let mut ret = vec![stem.to_string()];
+    let flags = dict.stem_flags.get(stem);
+    let (Some(flags), None) = (flags, &dict.lazy) else {
+        return ret;
+    };
+
+    let rules = EntryRules::new(&dict.affix_flags, flags);
+    let ctx = AfxContext {
+        affix_flags: &dict.affix_flags,
+        rule_flags: &dict.rule_flags,
+        complex_prefixes: dict.parsed_config.complex_prefixes(),
+    };
+    let mut words = WordList::new();
+    let mut substandard_words = WordList::new();
+    create_affixed_word_map(
+        stem,
+        &rules.prefix_rules,
+        &rules.suffix_rules,
+        &ctx,
+        None,
+        &mut words,
+        &mut substandard_words,
+    );
+
+    ret.extend(words.0.into_keys().map(String::from));
+    ret
+}
+
+/// Check whether any derivation in a word's metadata comes from `stem` and has all of
+/// the given morphs, like a single analysis from [`WordEntry::analyses`].
+///
+/// [`WordEntry::analyses`]: super::WordEntry::analyses
+fn metas_match(metas: &[Meta], stem: &str, morphs: &[MorphInfo]) -> bool {
+    split_derivations(metas).into_iter().any(|derivation| {
+        derivation
+            .iter()
+            .any(|m| m.stem() == stem || m.stem_rc().as_ref() == stem)
+            && morphs.iter().all(|morph| {
+                derivation
+                    .iter()
+                    .any(|m| m.source().morphs().any(|x| x == morph))
+            })
+    })
+}
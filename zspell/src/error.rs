@@ -5,8 +5,9 @@
 
 use std::fmt::Display;
 use std::num::ParseIntError;
+use std::ops::Range;
 
-use crate::affix::FlagType;
+use crate::affix::{Encoding, FlagType};
 use crate::dict::FlagValue;
 use crate::helpers::convertu32;
 
@@ -22,6 +23,10 @@ pub enum Error {
     Regex(regex::Error),
 
     Io(IoError),
+    /// No dictionary was given for a language code
+    UnknownLanguage(String),
+    /// A span is out of bounds, not on a `char` boundary, or overlaps another span
+    InvalidSpan(Range<usize>),
 }
 
 /// An error that occured while parsing, consisting of an error variant and a
@@ -75,6 +80,12 @@ pub enum BuildError {
     },
     /// A flag in a dictionary file does not match any known flags
     NonmatchingFlag { stem: String, flag: String },
+    /// The encoding given by `SET` is recognized, but decoding it is not supported
+    UnsupportedEncoding(Encoding),
+    /// Input bytes are not valid for the encoding given by `SET`
+    Decode(Encoding),
+    /// Data is not a valid compact dictionary, or was created by a different version
+    InvalidCompact,
 }
 
 /// An I/O error. This is a wrapper around [`std::io::ErrorKind`]
@@ -127,6 +138,11 @@ pub enum ParseErrorKind {
     Phonetic(usize),
     PartOfSpeech(String),
     DictEntry,
+    /// An alias reference (`AF` or `AM`) out of range of the defined table
+    AliasIndex {
+        index: usize,
+        count: usize,
+    },
     /// Regex error while parsing
     Regex(regex::Error),
 }
@@ -243,6 +259,12 @@ impl Display for Error {
             Error::Build(e) => write!(f, "build error: {e}"),
             Error::Regex(e) => write!(f, "regex error: {e}"),
             Error::Io(e) => write!(f, "io error: {e}"),
+            Error::UnknownLanguage(lang) => write!(f, "no dictionary for language '{lang}'"),
+            Error::InvalidSpan(range) => write!(
+                f,
+                "invalid span {}..{}: out of bounds, not on a character boundary, or overlapping",
+                range.start, range.end
+            ),
         }
     }
 }
@@ -307,6 +329,10 @@ impl Display for ParseErrorKind {
             ParseErrorKind::CompoundPattern => write!(f, "invalid compound pattern"),
             ParseErrorKind::Phonetic(n) => write!(f, "expected 2 items but got {n}"),
             ParseErrorKind::DictEntry => write!(f, "invalid dictionary entry"),
+            ParseErrorKind::AliasIndex { index, count } => write!(
+                f,
+                "alias {index} does not exist, expected a value between 1 and {count}"
+            ),
             ParseErrorKind::PartOfSpeech(s) => {
                 write!(f, "value '{s}' is not a known part of speech")
             }
@@ -358,6 +384,18 @@ impl Display for BuildError {
                 f,
                 "stem '{stem}' is marked with flag '{flag}' but it does not match any patterns"
             ),
+            BuildError::UnsupportedEncoding(enc) => write!(
+                f,
+                "encoding '{}' is not supported; convert the dictionary to UTF-8",
+                <&str>::from(enc.clone())
+            ),
+            BuildError::Decode(enc) => {
+                write!(f, "input is not valid {}", <&str>::from(enc.clone()))
+            }
+            BuildError::InvalidCompact => write!(
+                f,
+                "invalid compact dictionary; it may have been created by a different version"
+            ),
         }
     }
 }
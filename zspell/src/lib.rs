@@ -79,14 +79,14 @@
 //! // We can use the entry API to do the standard checks (word position and correctness),
 //! // but also to find word roots.
 //! let banana_entry = entries.next().unwrap();
-//! let banana_stems: Vec<&str> = banana_entry.stems().unwrap().collect();
+//! let banana_stems: Vec<_> = banana_entry.stems().unwrap().collect();
 //! assert_eq!(banana_entry.word(), "bananas");
 //! assert_eq!(banana_entry.index(), 0);
 //! assert_eq!(banana_entry.correct(), true);
 //! assert_eq!(banana_stems, ["banana"]);
 //!
 //! let rust_entry = entries.next().unwrap();
-//! let rust_stems: Vec<&str> = rust_entry.stems().unwrap().collect();
+//! let rust_stems: Vec<_> = rust_entry.stems().unwrap().collect();
 //! assert_eq!(rust_stems, ["rust"]);
 //! ```
 //!
@@ -111,6 +111,9 @@
 //! flags are subject to change, but the need for these flags will be removed as
 //! they are stabalized.
 //!
+//! The `parallel` feature parses and expands dictionary files on multiple threads
+//! when building (see `DictBuilder::parallel`).
+//!
 //! [Hunspell]: http://hunspell.github.io/
 //! [CLI docs]: https://pluots.github.io/zspell/
 #![forbid(unsafe_code)]
@@ -144,7 +147,10 @@ pub mod system;
 pub(crate) use affix::ParsedCfg;
 pub use affix::PartOfSpeech;
 #[doc(inline)]
-pub use dict::{DictBuilder, Dictionary, WordEntry, WordList};
+pub use dict::{
+    CompactDictionary, DictBuilder, Dictionary, DictionaryStack, FormKind, Misspelling,
+    MultiDictionary, StackEntry, Strategy, WordEntry, WordForm, WordList,
+};
 #[doc(inline)]
 pub use error::Error;
 pub use morph::{MorphInfo, MorphStr};
@@ -152,7 +158,7 @@ pub use morph::{MorphInfo, MorphStr};
 // Make some things public when benchmarking
 #[cfg(feature = "unstable-bench")]
 pub mod bench {
-    pub use super::affix::{affix_from_str, FlagType};
+    pub use super::affix::{affix_from_str, FlagType, ParsedCfg};
     pub use super::dict::DictEntry;
 }
 
@@ -4,6 +4,7 @@
 // use std::collections::HashSet;
 // use std::ffi::OsStr;
 // use std::path::{Component, Path, PathBuf};
+use std::path::Path;
 use std::{env, fs};
 
 use crate::error::{Error, IoError};
@@ -332,23 +333,46 @@ pub const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Error when can't find dictionary
 #[inline]
 pub fn create_dict_from_path(basepath: &str) -> Result<Dictionary, Error> {
+    load_dict(basepath, None)
+}
+
+/// Like [`create_dict_from_path`], but use a compiled cache file to speed up loading.
+/// The cache is created or updated if it is missing or outdated. See
+/// [`DictBuilder::cache_path`].
+///
+/// # Errors
+///
+/// Error when can't find dictionary
+#[inline]
+pub fn create_dict_from_path_cached(
+    basepath: &str,
+    cache_path: &Path,
+) -> Result<Dictionary, Error> {
+    load_dict(basepath, Some(cache_path))
+}
+
+fn load_dict(basepath: &str, cache_path: Option<&Path>) -> Result<Dictionary, Error> {
     let mut dict_file_path = basepath.to_owned();
     let mut affix_file_path = basepath.to_owned();
 
     dict_file_path.push_str(".dic");
     affix_file_path.push_str(".aff");
 
-    let aff_str = fs::read_to_string(&affix_file_path)
-        .map_err(|e| IoError::new(&affix_file_path, e.kind()))?;
+    // Files may use any encoding given by `SET`, so they are decoded by the builder
+    let aff_bytes =
+        fs::read(&affix_file_path).map_err(|e| IoError::new(&affix_file_path, e.kind()))?;
+
+    let dict_bytes =
+        fs::read(&dict_file_path).map_err(|e| IoError::new(&dict_file_path, e.kind()))?;
+    let mut builder = DictBuilder::new()
+        .config_bytes(&aff_bytes)
+        .dict_bytes(&dict_bytes);
 
-    let dict_str =
-        fs::read_to_string(&dict_file_path).map_err(|e| IoError::new(&dict_file_path, e.kind()))?;
-    let dict = DictBuilder::new()
-        .config_str(&aff_str)
-        .dict_str(&dict_str)
-        .build()?;
+    if let Some(path) = cache_path {
+        builder = builder.cache_path(path);
+    }
 
-    Ok(dict)
+    builder.build()
 }
 
 #[cfg(test)]